@@ -124,3 +124,25 @@ pub(crate) fn enabled_tasks(current: &OsStr) -> Vec<CompletionCandidate> {
             .map(|t| (t.slug().into(), t.task())),
     )
 }
+
+pub(crate) fn pinned_tasks(current: &OsStr) -> Vec<CompletionCandidate> {
+    let state = State::load().unwrap();
+    filter_candidate_tasks(
+        current,
+        state
+            .pinned_tasks()
+            .into_iter()
+            .map(|t| (t.slug().into(), t.task())),
+    )
+}
+
+pub(crate) fn unpinned_tasks(current: &OsStr) -> Vec<CompletionCandidate> {
+    let state = State::load().unwrap();
+    filter_candidate_tasks(
+        current,
+        state
+            .unpinned_tasks()
+            .into_iter()
+            .map(|t| (t.slug().into(), t.task())),
+    )
+}
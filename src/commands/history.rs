@@ -0,0 +1,171 @@
+use super::{ExecutableCommand, completion, parse_date};
+use crate::{
+    Error, Result, State,
+    history::{self, DayRecord},
+};
+use clap::{Args, Subcommand, ValueEnum};
+use clap_complete::ArgValueCompleter;
+use std::collections::BTreeMap;
+use time::{Date, Duration};
+
+/// The shades used for each day, from least to most completed.
+const SHADES: [char; 5] = ['·', '░', '▒', '▓', '█'];
+/// How far back the calendar goes if no start date is given.
+const DEFAULT_WEEKS: i64 = 26;
+const WEEKDAY_LABELS: [&str; 7] = ["Mon", "", "Wed", "", "Fri", "", ""];
+const LABEL_WIDTH: usize = 4;
+
+#[derive(Debug, Subcommand)]
+#[command(rename_all = "kebab")]
+pub enum HistoryCommands {
+    /// Show a heatmap of how much of each day's tasks were completed.
+    #[command(alias = "cal")]
+    Calendar(CalendarCommand),
+}
+
+impl ExecutableCommand for HistoryCommands {
+    fn execute(self, state: State) -> Result<()> {
+        match self {
+            Self::Calendar(cmd) => cmd.execute(state),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, ValueEnum)]
+pub enum ShadeBy {
+    /// The fraction of the day's tasks that were completed.
+    #[default]
+    Tasks,
+    /// The fraction of the day's spoons that were completed.
+    Spoons,
+}
+
+#[derive(Debug, Args)]
+pub struct CalendarCommand {
+    #[arg(long, value_parser = parse_date)]
+    /// The first day to show. Defaults to 26 weeks before the last day.
+    pub from: Option<Date>,
+    #[arg(long, value_parser = parse_date)]
+    /// The last day to show. Defaults to today.
+    pub until: Option<Date>,
+    #[arg(long = "tag")]
+    /// Only count tasks with any of these tags.
+    pub tags: Vec<String>,
+    #[arg(long = "task", add = ArgValueCompleter::new(completion::all_tasks))]
+    /// Only count these tasks.
+    pub tasks: Vec<String>,
+    #[arg(short, long, value_enum, default_value_t)]
+    /// What to shade each day by.
+    pub by: ShadeBy,
+}
+
+impl CalendarCommand {
+    fn includes(&self, state: &State, slug: &str) -> bool {
+        (self.tasks.is_empty() || self.tasks.iter().any(|t| t == slug))
+            && (self.tags.is_empty()
+                || state
+                    .get_task(slug)
+                    .is_some_and(|t| t.tags().iter().any(|t| self.tags.contains(t))))
+    }
+
+    fn amount(&self, state: &State, slug: &str) -> f64 {
+        match self.by {
+            ShadeBy::Tasks => 1.0,
+            ShadeBy::Spoons => state.get_task(slug).map_or(0, |t| t.spoons()) as f64,
+        }
+    }
+
+    /// How much of the day's tasks were completed, between 0 and 1, or `None` if there weren't
+    /// any tasks that day. Multi-day tasks count towards every day they were part of.
+    fn completion(&self, state: &State, record: &DayRecord) -> Option<f64> {
+        let (mut total, mut completed) = (0.0, 0.0);
+        for slug in record.tasks.union(&record.completed) {
+            if !self.includes(state, slug) {
+                continue;
+            }
+            let amount = self.amount(state, slug);
+            total += amount;
+            if record.completed.contains(slug) {
+                completed += amount;
+            }
+        }
+        if total > 0.0 {
+            Some(completed / total)
+        } else if record.completed.iter().any(|s| self.includes(state, s)) {
+            // Only tasks without any spoons were completed.
+            Some(1.0)
+        } else {
+            None
+        }
+    }
+
+    fn shade(&self, state: &State, record: Option<&DayRecord>) -> char {
+        match record.and_then(|r| self.completion(state, r)) {
+            None => ' ',
+            Some(c) if c <= 0.0 => SHADES[0],
+            Some(c) => SHADES[((c * 4.0).ceil() as usize).clamp(1, 4)],
+        }
+    }
+}
+
+/// Draws the month names above the first week that each month starts in.
+fn month_header(start: Date, weeks: i64, from: Date) -> String {
+    let mut header = " ".repeat(LABEL_WIDTH);
+    let mut last_month = None;
+    for week in 0..weeks {
+        let date = (start + Duration::weeks(week)).max(from);
+        let column = LABEL_WIDTH + week as usize * 2;
+        if last_month != Some(date.month()) && header.chars().count() <= column {
+            header.push_str(&" ".repeat(column - header.chars().count()));
+            header.extend(date.month().to_string().chars().take(3));
+            header.push(' ');
+        }
+        last_month = Some(date.month());
+    }
+    String::from(header.trim_end())
+}
+
+impl ExecutableCommand for CalendarCommand {
+    fn execute(self, state: State) -> Result<()> {
+        let until = self.until.unwrap_or_else(|| state.todays_date());
+        let from = self
+            .from
+            .unwrap_or_else(|| until - Duration::weeks(DEFAULT_WEEKS) + Duration::DAY);
+        if from > until {
+            return Err(Error::simple("--from must be on or before --until"));
+        }
+        if let Some(missing) = self.tasks.iter().find(|t| state.get_task(t).is_none()) {
+            return Err(Error::task_not_found(missing));
+        }
+        let events = history::read_since(Some(from))?;
+        let days: BTreeMap<_, _> = history::days_until_today(&events, &state)
+            .into_iter()
+            .filter(|(date, _)| (from..=until).contains(date))
+            .collect();
+
+        let start = from - Duration::days(from.weekday().number_days_from_monday() as i64);
+        let weeks = (until - start).whole_weeks() + 1;
+        println!("{}", month_header(start, weeks, from));
+        for (weekday, label) in WEEKDAY_LABELS.iter().enumerate() {
+            let mut line = format!("{label:<LABEL_WIDTH$}");
+            for week in 0..weeks {
+                let date = start + Duration::weeks(week) + Duration::days(weekday as i64);
+                let shade = if (from..=until).contains(&date) {
+                    self.shade(&state, days.get(&date))
+                } else {
+                    ' '
+                };
+                line.push(shade);
+                line.push(' ');
+            }
+            println!("{}", line.trim_end());
+        }
+        println!();
+        println!(
+            "{:LABEL_WIDTH$}Less {} More",
+            "",
+            SHADES.map(String::from).join(" ")
+        );
+        Ok(())
+    }
+}
@@ -4,6 +4,10 @@ use std::ffi::OsStr;
 use time::{Date, format_description::well_known::Iso8601};
 
 pub mod config;
+pub mod history;
+use history::HistoryCommands;
+pub mod stats;
+use stats::StatsCommand;
 pub mod tasks;
 use tasks::TaskCommands;
 pub mod today;
@@ -39,9 +43,13 @@ impl Default for Cli {
 #[derive(Debug, Subcommand)]
 pub enum Commands {
     #[command(subcommand)]
-    Tasks(TaskCommands),
+    Tasks(Box<TaskCommands>),
     #[command(subcommand)]
     Today(TodayCommands),
+    #[command(subcommand)]
+    History(HistoryCommands),
+    /// Show how often tasks were chosen, completed and refreshed.
+    Stats(StatsCommand),
 }
 
 pub trait ExecutableCommand {
@@ -53,6 +61,8 @@ impl ExecutableCommand for Commands {
         match self {
             Self::Tasks(cmd) => cmd.execute(state),
             Self::Today(cmd) => cmd.execute(state),
+            Self::History(cmd) => cmd.execute(state),
+            Self::Stats(cmd) => cmd.execute(state),
         }
     }
 }
@@ -0,0 +1,95 @@
+use super::{ExecutableCommand, parse_date};
+use crate::{
+    Result, State, history,
+    stats::{Counts, Stats},
+};
+use clap::{Args, ValueEnum};
+use cli_table::{Cell, Style, Table, TableStruct};
+use std::{fmt::Display, io};
+use time::Date;
+
+#[derive(Debug, Clone, Copy, Default, ValueEnum)]
+pub enum StatsFormat {
+    #[default]
+    Table,
+    Yaml,
+    Json,
+}
+
+fn format_rate(counts: &Counts) -> String {
+    counts
+        .completion_rate()
+        .map(|r| format!("{:.0}%", r * 100.0))
+        .unwrap_or_else(|| String::from("-"))
+}
+
+fn counts_table<K, I>(title: &str, rows: I) -> TableStruct
+where
+    K: Display,
+    I: IntoIterator<Item = (K, Counts)>,
+{
+    rows.into_iter()
+        .map(|(key, counts)| {
+            vec![
+                key.cell(),
+                counts.chosen.cell(),
+                counts.completed.cell(),
+                counts.refreshed.cell(),
+                format_rate(&counts).cell(),
+            ]
+        })
+        .collect::<Vec<_>>()
+        .table()
+        .title(vec![
+            title.cell().bold(true),
+            "Chosen".cell().bold(true),
+            "Completed".cell().bold(true),
+            "Refreshed".cell().bold(true),
+            "Rate".cell().bold(true),
+        ])
+}
+
+fn print_tables(stats: &Stats) -> Result<()> {
+    cli_table::print_stdout(counts_table(
+        "Task",
+        stats.tasks.iter().map(|(k, c)| (k, *c)),
+    ))?;
+    if !stats.tags.is_empty() {
+        cli_table::print_stdout(counts_table("Tag", stats.tags.iter().map(|(k, c)| (k, *c))))?;
+    }
+    cli_table::print_stdout(counts_table(
+        "Weekday",
+        stats.weekdays.iter().map(|(k, c)| (k.0, *c)),
+    ))?;
+    cli_table::print_stdout(counts_table("Total", [("All", stats.total)]))?;
+    Ok(())
+}
+
+#[derive(Debug, Args)]
+pub struct StatsCommand {
+    #[arg(long, value_parser = parse_date)]
+    /// Only count events on or after this date.
+    pub from: Option<Date>,
+    #[arg(long, value_parser = parse_date)]
+    /// Only count events on or before this date.
+    pub until: Option<Date>,
+    #[arg(short, long, value_enum, default_value_t)]
+    /// How to print the stats.
+    pub format: StatsFormat,
+}
+
+impl ExecutableCommand for StatsCommand {
+    fn execute(self, state: State) -> Result<()> {
+        let events = history::read_since(self.from)?;
+        let stats = Stats::from_events(&events, &state, self.from, self.until);
+        match self.format {
+            StatsFormat::Table => print_tables(&stats)?,
+            StatsFormat::Yaml => serde_norway::to_writer(io::stdout(), &stats)?,
+            StatsFormat::Json => {
+                serde_json::to_writer_pretty(io::stdout(), &stats)?;
+                println!();
+            }
+        }
+        Ok(())
+    }
+}
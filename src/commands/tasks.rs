@@ -2,14 +2,17 @@ use super::{ExecutableCommand, completion, parse_date};
 use crate::{
     Error, Result, State,
     error::RanddGoalsError,
-    task::{TaskBuilder, TaskConfig},
+    history,
+    streak::Streaks,
+    task::{Recurrence, Target, TaskBuilder, TaskConfig, TaskInfo, TaskVariant},
+    util::{MonthDay, parse_weekday},
 };
 use camino::Utf8PathBuf;
 use clap::{Args, Subcommand};
 use clap_complete::{ArgValueCompleter, PathCompleter};
 use cli_table::{Cell, Table};
 use std::{collections::BTreeMap, fs, io};
-use time::Date;
+use time::{Date, Weekday};
 
 #[derive(Debug, Subcommand)]
 #[command(rename_all = "kebab")]
@@ -33,6 +36,10 @@ pub enum TaskCommands {
     Enable(EnableTaskCommand),
     /// Disable task(s).
     Disable(DisableTaskCommand),
+    /// Pin task(s) so that they're part of today's tasks every day.
+    Pin(PinTaskCommand),
+    /// Unpin task(s).
+    Unpin(UnpinTaskCommand),
     /// Mark task(s) as complete.
     #[command(aliases = ["c", "done"])]
     Complete(CompleteTaskCommand),
@@ -65,6 +72,8 @@ impl ExecutableCommand for TaskCommands {
             Self::Details(cmd) => cmd.execute(state),
             Self::Enable(cmd) => cmd.execute(state),
             Self::Disable(cmd) => cmd.execute(state),
+            Self::Pin(cmd) => cmd.execute(state),
+            Self::Unpin(cmd) => cmd.execute(state),
             Self::Remove(cmd) => cmd.execute(state),
             Self::Complete(cmd) => cmd.execute(state),
             Self::Import(cmd) => cmd.execute(state),
@@ -132,6 +141,40 @@ pub struct AddTaskCommand {
     #[arg(long = "tag")]
     /// Any tags to associate with the task.
     pub tags: Vec<String>,
+    #[arg(long = "exclusion-group")]
+    /// Any groups of tasks that this task can't be picked on the same day as.
+    pub exclusion_groups: Vec<String>,
+    #[arg(long = "weekday", value_parser = parse_weekday)]
+    /// The days of the week the task can be chosen on.
+    pub weekdays: Vec<Weekday>,
+    #[arg(long)]
+    /// The first day of the year the task can be chosen on (MM-DD).
+    pub available_from: Option<MonthDay>,
+    #[arg(long)]
+    /// The last day of the year the task can be chosen on (MM-DD).
+    pub available_until: Option<MonthDay>,
+    #[arg(short, long)]
+    /// An iCalendar recurrence rule for when the task should always be picked.
+    pub recurrence: Option<Recurrence>,
+    #[arg(long = "variant")]
+    /// Concrete options for the task, one of which is chosen when the task is picked.
+    pub variants: Vec<TaskVariant>,
+    #[arg(long = "step")]
+    /// Ordered steps for the task, which it moves through each time it's completed.
+    pub steps: Vec<String>,
+    #[arg(long)]
+    /// A numeric goal for the task, such as "20 reps".
+    pub target: Option<Target>,
+    #[arg(long = "item")]
+    /// Items to tick off individually; the task is completed once all of them are checked.
+    pub checklist: Vec<String>,
+    #[arg(long)]
+    /// How many days the task stays part of today's tasks once it's picked, until it's completed.
+    pub duration_days: Option<u32>,
+    #[arg(long, num_args = 0..=1, require_equals = true, default_missing_value = "true")]
+    /// Count a multi-day task's spoons towards every day it's part of, instead of only the day
+    /// it was picked.
+    pub spoons_per_day: Option<bool>,
     #[arg(short, long)]
     /// A more detailed description of the task.
     pub description: Option<String>,
@@ -151,9 +194,13 @@ pub struct AddTaskCommand {
 
 impl_into_task_builder! {
     AddTaskCommand {
-        required: (task, tags, description, max_occurrences, min_frequency),
+        required: (
+            task, tags, exclusion_groups, weekdays,
+            description, max_occurrences, min_frequency, available_from, available_until,
+            recurrence, variants, steps, target, checklist, duration_days
+        ),
         optional: (slug),
-        copy: (weight),
+        copy: (weight, spoons, spoons_per_day),
     }
 }
 
@@ -173,6 +220,40 @@ pub struct UpsertTaskCommand {
     #[arg(long = "tag")]
     /// Any tags to associate with the task.
     pub tags: Vec<String>,
+    #[arg(long = "exclusion-group")]
+    /// Any groups of tasks that this task can't be picked on the same day as.
+    pub exclusion_groups: Vec<String>,
+    #[arg(long = "weekday", value_parser = parse_weekday)]
+    /// The days of the week the task can be chosen on.
+    pub weekdays: Vec<Weekday>,
+    #[arg(long)]
+    /// The first day of the year the task can be chosen on (MM-DD).
+    pub available_from: Option<MonthDay>,
+    #[arg(long)]
+    /// The last day of the year the task can be chosen on (MM-DD).
+    pub available_until: Option<MonthDay>,
+    #[arg(short, long)]
+    /// An iCalendar recurrence rule for when the task should always be picked.
+    pub recurrence: Option<Recurrence>,
+    #[arg(long = "variant")]
+    /// Concrete options for the task, one of which is chosen when the task is picked.
+    pub variants: Vec<TaskVariant>,
+    #[arg(long = "step")]
+    /// Ordered steps for the task, which it moves through each time it's completed.
+    pub steps: Vec<String>,
+    #[arg(long)]
+    /// A numeric goal for the task, such as "20 reps".
+    pub target: Option<Target>,
+    #[arg(long = "item")]
+    /// Items to tick off individually; the task is completed once all of them are checked.
+    pub checklist: Vec<String>,
+    #[arg(long)]
+    /// How many days the task stays part of today's tasks once it's picked, until it's completed.
+    pub duration_days: Option<u32>,
+    #[arg(long, num_args = 0..=1, require_equals = true, default_missing_value = "true")]
+    /// Count a multi-day task's spoons towards every day it's part of, instead of only the day
+    /// it was picked.
+    pub spoons_per_day: Option<bool>,
     #[arg(short, long)]
     /// A more detailed description of the task.
     pub description: Option<String>,
@@ -196,9 +277,13 @@ pub struct UpsertTaskCommand {
 
 impl_into_task_builder! {
     UpsertTaskCommand {
-        required: (slug, tags, description, max_occurrences, min_frequency),
+        required: (
+            slug, tags, exclusion_groups, weekdays,
+            description, max_occurrences, min_frequency, available_from, available_until,
+            recurrence, variants, steps, target, checklist, duration_days
+        ),
         optional: (task),
-        copy: (weight),
+        copy: (weight, spoons, spoons_per_day),
     }
 }
 
@@ -218,6 +303,40 @@ pub struct UpdateTaskCommand {
     #[arg(long = "tag")]
     /// Any tags to associate with the task.
     pub tags: Vec<String>,
+    #[arg(long = "exclusion-group")]
+    /// Any groups of tasks that this task can't be picked on the same day as.
+    pub exclusion_groups: Vec<String>,
+    #[arg(long = "weekday", value_parser = parse_weekday)]
+    /// The days of the week the task can be chosen on.
+    pub weekdays: Vec<Weekday>,
+    #[arg(long)]
+    /// The first day of the year the task can be chosen on (MM-DD).
+    pub available_from: Option<MonthDay>,
+    #[arg(long)]
+    /// The last day of the year the task can be chosen on (MM-DD).
+    pub available_until: Option<MonthDay>,
+    #[arg(short, long)]
+    /// An iCalendar recurrence rule for when the task should always be picked.
+    pub recurrence: Option<Recurrence>,
+    #[arg(long = "variant")]
+    /// Concrete options for the task, one of which is chosen when the task is picked.
+    pub variants: Vec<TaskVariant>,
+    #[arg(long = "step")]
+    /// Ordered steps for the task, which it moves through each time it's completed.
+    pub steps: Vec<String>,
+    #[arg(long)]
+    /// A numeric goal for the task, such as "20 reps".
+    pub target: Option<Target>,
+    #[arg(long = "item")]
+    /// Items to tick off individually; the task is completed once all of them are checked.
+    pub checklist: Vec<String>,
+    #[arg(long)]
+    /// How many days the task stays part of today's tasks once it's picked, until it's completed.
+    pub duration_days: Option<u32>,
+    #[arg(long, num_args = 0..=1, require_equals = true, default_missing_value = "true")]
+    /// Count a multi-day task's spoons towards every day it's part of, instead of only the day
+    /// it was picked.
+    pub spoons_per_day: Option<bool>,
     #[arg(short, long)]
     /// A more detailed description of the task.
     pub description: Option<String>,
@@ -240,9 +359,13 @@ pub struct UpdateTaskCommand {
 
 impl_into_task_builder! {
     UpdateTaskCommand {
-        required: (slug, tags, description, max_occurrences, min_frequency),
+        required: (
+            slug, tags, exclusion_groups, weekdays,
+            description, max_occurrences, min_frequency, available_from, available_until,
+            recurrence, variants, steps, target, checklist, duration_days
+        ),
         optional: (task),
-        copy: (weight),
+        copy: (weight, spoons, spoons_per_day),
     }
 }
 
@@ -272,7 +395,7 @@ impl ExecutableCommand for EnableTaskCommand {
 //TODO add options
 pub struct DisableTaskCommand {
     //TODO add date completer
-    #[arg(short, long, value_parser = parse_date, conflicts_with = "until")]
+    #[arg(short, long, value_parser = parse_date, conflicts_with = "for_")]
     /// Disable the task(s) until a certain date (TODO).
     pub until: Option<Date>,
     #[arg(short, long = "for")]
@@ -290,6 +413,34 @@ impl ExecutableCommand for DisableTaskCommand {
     }
 }
 
+#[derive(Debug, Args)]
+pub struct PinTaskCommand {
+    #[arg(add = ArgValueCompleter::new(completion::unpinned_tasks))]
+    /// The task(s) to pin.
+    pub tasks: Vec<String>,
+}
+
+impl ExecutableCommand for PinTaskCommand {
+    fn execute(self, state: State) -> Result<()> {
+        state.pin_tasks(self.tasks)?;
+        state.save()
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct UnpinTaskCommand {
+    #[arg(add = ArgValueCompleter::new(completion::pinned_tasks))]
+    /// The task(s) to unpin.
+    pub tasks: Vec<String>,
+}
+
+impl ExecutableCommand for UnpinTaskCommand {
+    fn execute(self, state: State) -> Result<()> {
+        state.unpin_tasks(self.tasks)?;
+        state.save()
+    }
+}
+
 #[derive(Debug, Args)]
 pub struct TaskDetailsCommand {
     #[arg(add = ArgValueCompleter::new(completion::all_tasks))]
@@ -304,11 +455,17 @@ impl ExecutableCommand for TaskDetailsCommand {
         } else {
             self.tasks
         };
+        let streaks = Streaks::load(&state);
         let infos: BTreeMap<_, _> = tasks
             .into_iter()
             .map(|s| state.get_task(&s).ok_or_else(|| Error::task_not_found(&s)))
             //TODO handle missing
-            .flat_map(|r| r.map(|t| t.info(&state)))
+            .flat_map(|r| {
+                r.map(|t| TaskInfo {
+                    streak: Some(streaks.task(t.slug())).filter(|s| *s > 0),
+                    ..t.info(&state)
+                })
+            })
             .map(|i| (i.slug.clone(), i))
             .collect();
         let stdout = io::stdout();
@@ -338,6 +495,14 @@ pub struct CompleteTaskCommand {
     #[arg(short, long)]
     /// Mark all of today's tasks as complete.
     pub all: bool,
+    #[arg(long, conflicts_with = "all")]
+    /// Log an amount towards the tasks' targets instead. Tasks are only completed once their
+    /// target is met.
+    pub amount: Option<f64>,
+    #[arg(short, long, value_parser = parse_date, conflicts_with = "amount")]
+    /// Complete the tasks on an earlier day, such as when they weren't marked as complete before
+    /// the cut-off. With `--all`, all of that day's tasks are completed.
+    pub date: Option<Date>,
     #[arg(add = ArgValueCompleter::new(completion::uncompleted_tasks), conflicts_with = "all")]
     /// The task(s) to complete.
     pub tasks: Vec<String>,
@@ -345,17 +510,29 @@ pub struct CompleteTaskCommand {
 
 impl ExecutableCommand for CompleteTaskCommand {
     fn execute(self, state: State) -> Result<()> {
+        if let Some(date) = self.date.filter(|d| *d != state.todays_date()) {
+            if date > state.todays_date() {
+                return Err(Error::simple("Tasks can't be completed on future days."));
+            }
+            let record = history::read_day(date)?;
+            let tasks = if self.all {
+                record.tasks.iter().cloned().collect()
+            } else {
+                self.tasks
+            };
+            tasks
+                .into_iter()
+                .try_for_each(|slug| state.complete_task_on(&slug, date, &record))?;
+            return state.save();
+        }
         let tasks = if self.all {
             state.todays_tasks().into()
         } else {
             self.tasks
         };
-        tasks.into_iter().try_for_each(|slug| {
-            state
-                .get_task(&slug)
-                .ok_or_else(|| Error::task_not_found(&slug))
-                .map(|task| task.complete())
-        })?;
+        tasks
+            .into_iter()
+            .try_for_each(|slug| state.complete_task(&slug, self.amount))?;
         state.save()
     }
 }
@@ -402,8 +579,8 @@ impl ExecutableCommand for ImportTaskCommand {
                 }
             }
         }
-        println!("Imported task(s).");
         state.save()?;
+        println!("Imported task(s).");
         Ok(())
     }
 }
@@ -1,7 +1,9 @@
-use super::{ExecutableCommand, completion, tasks::CompleteTaskCommand};
+use super::{ExecutableCommand, completion, parse_date, tasks::CompleteTaskCommand};
 use crate::{
     Error, Result, State,
-    picker::pick_todays_tasks,
+    history::{self, EventKind},
+    picker::{PickOutcome, pick_todays_tasks, reroll_variants},
+    streak::Streaks,
     task::{TaskInfo, TaskSet, TaskStatus},
 };
 use clap::{Args, Subcommand};
@@ -12,6 +14,7 @@ use std::{
     collections::{BTreeMap, BTreeSet},
     io,
 };
+use time::Date;
 
 #[derive(Debug, Serialize)]
 struct TaskListItem {
@@ -21,6 +24,18 @@ struct TaskListItem {
     status: TaskStatus,
     #[serde(skip_serializing_if = "std::option::Option::is_none")]
     description: Option<String>,
+    #[serde(skip_serializing_if = "std::option::Option::is_none")]
+    variant: Option<String>,
+    #[serde(skip_serializing_if = "std::option::Option::is_none")]
+    step: Option<String>,
+    #[serde(skip_serializing_if = "std::option::Option::is_none")]
+    progress: Option<String>,
+    #[serde(skip_serializing_if = "std::vec::Vec::is_empty")]
+    checklist: Vec<String>,
+    #[serde(skip_serializing_if = "std::option::Option::is_none")]
+    day: Option<String>,
+    #[serde(skip_serializing_if = "std::option::Option::is_none")]
+    streak: Option<u32>,
 }
 
 impl From<TaskInfo> for TaskListItem {
@@ -30,6 +45,19 @@ impl From<TaskInfo> for TaskListItem {
             task: info.task,
             status: info.status,
             description: info.description,
+            variant: info.variant,
+            step: info.step,
+            progress: info.progress,
+            checklist: info
+                .checklist
+                .iter()
+                .map(|i| {
+                    let mark = if info.checked.contains(i) { 'x' } else { ' ' };
+                    format!("[{mark}] {i}")
+                })
+                .collect(),
+            day: info.day,
+            streak: info.streak,
         }
     }
 }
@@ -38,11 +66,15 @@ fn get_task_list_items<S: AsRef<TaskSet>>(
     state: &State,
     tasks: S,
 ) -> Result<BTreeMap<String, TaskListItem>> {
+    let streaks = Streaks::load(state);
     Ok(tasks
         .as_ref()
         .resolve(state)?
         .into_iter()
-        .map(|t| t.info(state))
+        .map(|t| TaskInfo {
+            streak: Some(streaks.task(t.slug())).filter(|s| *s > 0),
+            ..t.info(state)
+        })
         .map(|i| (i.slug.clone(), i.into()))
         .collect())
 }
@@ -54,6 +86,22 @@ fn get_and_print_task_list_items<S: AsRef<TaskSet>>(state: &State, tasks: S) ->
     Ok(())
 }
 
+fn warn_unmet_quotas(outcome: &PickOutcome) {
+    for unmet in outcome.unmet_quotas.iter() {
+        eprintln!("Warning: {unmet}.");
+    }
+}
+
+fn print_streak(streaks: &Streaks) {
+    match streaks.freezes_left {
+        Some(freezes) => eprintln!(
+            "Streak: {} day(s), {freezes} freeze(s) left this month.",
+            streaks.days
+        ),
+        None => eprintln!("Streak: {} day(s).", streaks.days),
+    }
+}
+
 #[derive(Debug, Subcommand)]
 #[command(rename_all = "kebab")]
 pub enum TodayCommands {
@@ -69,6 +117,8 @@ pub enum TodayCommands {
     /// Mark task(s) as complete.
     #[command(aliases = ["c", "done"])]
     Complete(CompleteTaskCommand),
+    /// Check off items in one of today's tasks' checklist.
+    Check(CheckTaskCommand),
 }
 
 impl ExecutableCommand for TodayCommands {
@@ -79,6 +129,7 @@ impl ExecutableCommand for TodayCommands {
             Self::Refresh(cmd) => cmd.execute(state),
             Self::Reset(cmd) => cmd.execute(state),
             Self::Complete(cmd) => cmd.execute(state),
+            Self::Check(cmd) => cmd.execute(state),
         }
     }
 }
@@ -91,19 +142,79 @@ pub struct GetTodaysTasksCommand {
     #[arg(short, long, conflicts_with = "notify")]
     /// Whether or not to print to to the console.
     pub quiet: bool,
+    #[arg(long)]
+    /// The seed to pick any new tasks with, instead of the configured one.
+    pub seed: Option<u64>,
+    #[arg(short, long, value_parser = parse_date, conflicts_with_all = ["notify", "seed"])]
+    /// Get the tasks from an earlier day instead, without picking any new tasks.
+    pub date: Option<Date>,
+}
+
+/// Prints an earlier day's tasks from the history, along with whether they were completed.
+fn print_earlier_tasks(state: &State, date: Date) -> Result<()> {
+    if date > state.todays_date() {
+        return Err(Error::simple(
+            "Tasks haven't been picked for future days yet.",
+        ));
+    }
+    let record = history::read_day(date)?;
+    let task_items: BTreeMap<_, _> = record
+        .tasks
+        .iter()
+        .map(|slug| {
+            let task = state.get_task(slug);
+            let picked_as = record.picked_as.get(slug).cloned().unwrap_or_default();
+            let item = TaskListItem {
+                slug: slug.clone(),
+                task: picked_as
+                    .title
+                    .unwrap_or_else(|| task.map_or_else(|| slug.clone(), |t| t.task())),
+                status: if record.completed.contains(slug) {
+                    TaskStatus::Complete
+                } else {
+                    TaskStatus::Incomplete
+                },
+                description: task.and_then(|t| t.description()),
+                variant: picked_as.variant,
+                step: None,
+                progress: None,
+                checklist: Vec::new(),
+                day: None,
+                streak: None,
+            };
+            (slug.clone(), item)
+        })
+        .collect();
+    serde_norway::to_writer(io::stdout(), &task_items)?;
+    Ok(())
 }
 
 impl ExecutableCommand for GetTodaysTasksCommand {
     fn execute(self, mut state: State) -> Result<()> {
-        if pick_todays_tasks(&mut state)? {
+        if let Some(date) = self.date.filter(|d| *d != state.todays_date()) {
+            return if self.quiet {
+                Ok(())
+            } else {
+                print_earlier_tasks(&state, date)
+            };
+        }
+        let outcome = pick_todays_tasks(&mut state, self.seed)?;
+        if outcome.changed() {
             state.save()?;
+            if !self.quiet {
+                eprintln!("Stopped picking tasks: {}.", outcome.limited_by);
+            }
+        }
+        if !self.quiet {
+            warn_unmet_quotas(&outcome);
+            print_streak(&Streaks::load(&state));
         }
         if self.notify {
             let mut task_strings: Vec<_> = state
                 .todays_tasks()
                 .resolve(&state)?
                 .into_iter()
-                .map(|t| format!(" - {}", t.task()))
+                .map(|t| format!(" - {}", t.rendered_task()))
                 .collect();
             notify_rust::Notification::new()
                 .summary("Today's random tasks")
@@ -133,6 +244,13 @@ pub struct RefreshTodaysTasksCommand {
     #[arg(short, long)]
     /// Whether or not all completed tasks should also be refreshed
     pub completed: bool,
+    #[arg(short, long)]
+    /// Only choose new variants for the tasks instead of replacing them. If no tasks are given,
+    /// all of today's tasks with variants are refreshed.
+    pub variant: bool,
+    #[arg(long)]
+    /// The seed to pick the new tasks with, instead of the configured one.
+    pub seed: Option<u64>,
     #[arg(add = ArgValueCompleter::new(completion::todays_tasks))]
     pub tasks: Vec<String>,
 }
@@ -148,29 +266,78 @@ impl ExecutableCommand for RefreshTodaysTasksCommand {
                 }
             }
         }
+        if self.variant {
+            if tasks.is_empty() {
+                for task in state.todays_tasks().resolve(&state)? {
+                    if task.has_variants() {
+                        tasks.insert(String::from(task.slug()));
+                    }
+                }
+            }
+            reroll_variants(&mut state, &tasks, self.seed)?;
+            state.save()?;
+            let tasks: TaskSet = tasks.into_iter().collect();
+            return get_and_print_task_list_items(&state, &tasks);
+        }
         for task in tasks {
             if !state.todays_tasks_mut().remove(&task) {
                 return Err(Error::task_not_found(task));
             }
+            state.record(EventKind::Refreshed { task });
         }
         let old_tasks = state.todays_tasks().clone();
-        if pick_todays_tasks(&mut state)? {
+        let outcome = pick_todays_tasks(&mut state, self.seed)?;
+        if outcome.changed() {
             state.save()?;
         }
+        warn_unmet_quotas(&outcome);
         let new_tasks = state.todays_tasks() - &old_tasks;
         get_and_print_task_list_items(&state, &new_tasks)
     }
 }
 
 #[derive(Debug, Args)]
-pub struct ResetTodaysTasksCommand {}
+pub struct ResetTodaysTasksCommand {
+    #[arg(long)]
+    /// The seed to pick the new tasks with, instead of the configured one.
+    pub seed: Option<u64>,
+}
 
 impl ExecutableCommand for ResetTodaysTasksCommand {
     fn execute(self, mut state: State) -> Result<()> {
+        for task in state.todays_tasks().iter() {
+            state.record(EventKind::Reset { task: task.clone() });
+        }
         state.todays_tasks_mut().clear();
-        if pick_todays_tasks(&mut state)? {
+        let outcome = pick_todays_tasks(&mut state, self.seed)?;
+        if outcome.changed() {
             state.save()?;
         }
+        warn_unmet_quotas(&outcome);
         get_and_print_task_list_items(&state, state.todays_tasks())
     }
 }
+
+#[derive(Debug, Args)]
+pub struct CheckTaskCommand {
+    #[arg(short, long)]
+    /// Uncheck the items instead.
+    pub uncheck: bool,
+    #[arg(add = ArgValueCompleter::new(completion::todays_tasks))]
+    /// The task the items belong to.
+    pub task: String,
+    #[arg(required = true)]
+    /// The item(s) to check, either by name or by position (starting from 1).
+    pub items: Vec<String>,
+}
+
+impl ExecutableCommand for CheckTaskCommand {
+    fn execute(self, state: State) -> Result<()> {
+        for item in self.items.iter() {
+            state.check_task(&self.task, item, !self.uncheck)?;
+        }
+        state.save()?;
+        let tasks: TaskSet = [&self.task].into_iter().collect();
+        get_and_print_task_list_items(&state, &tasks)
+    }
+}
@@ -1,13 +1,17 @@
-use crate::{CONFIG_FILE_PATH, Error, RcCell, Result, TaskConfig, util::now_with_cutoff};
+use crate::{
+    CONFIG_FILE_PATH, Error, RcCell, Result, TaskConfig,
+    picker::PickStrategy,
+    util::{now_with_cutoff, stable_hash},
+};
 use getset::Getters;
 use serde::{Deserialize, Serialize};
 use std::{
     cell::OnceCell,
-    collections::HashMap,
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
     fs::{self, OpenOptions},
 };
 use strum::EnumIs;
-use time::{Date, Duration, OffsetDateTime, Time, UtcOffset, macros::time};
+use time::{Date, Duration, OffsetDateTime, Time, UtcOffset, Weekday, macros::time};
 
 lazy_static! {
     pub static ref DEFAULT_CUT_OFF: Time = time!(04:00);
@@ -21,6 +25,38 @@ pub struct Config {
     tasks: Vec<RcCell<TaskConfig>>,
     cut_off: Time,
     limit_by: LimitTasksBy,
+    #[serde(default, skip_serializing_if = "std::option::Option::is_none")]
+    limit_schedule: Option<LimitSchedule>,
+    /// If set, tasks are picked using a seed derived from this secret and the effective date, so
+    /// that the same inputs always result in the same tasks.
+    #[serde(default, skip_serializing_if = "std::option::Option::is_none")]
+    seed_secret: Option<String>,
+    #[serde(default, skip_serializing_if = "PickStrategy::is_weighted_random")]
+    strategy: PickStrategy,
+    /// Boosts tasks' weights the longer it's been since they were last chosen.
+    #[serde(default, skip_serializing_if = "std::option::Option::is_none")]
+    staleness: Option<StalenessBoost>,
+    /// Adjusts tasks' weights based on how often they're completed after being chosen.
+    #[serde(default, skip_serializing_if = "std::option::Option::is_none")]
+    adaptive: Option<AdaptiveWeights>,
+    /// The minimum and/or maximum number of tasks with a tag that can be picked each day.
+    #[serde(default, skip_serializing_if = "std::collections::BTreeMap::is_empty")]
+    tag_quotas: BTreeMap<String, TagQuota>,
+    /// Groups of tasks that can't be picked on the same day as each other, in addition to any
+    /// groups declared by the tasks themselves.
+    #[serde(default, skip_serializing_if = "std::collections::BTreeMap::is_empty")]
+    exclusion_groups: BTreeMap<String, Vec<String>>,
+    /// Whether tasks picked because of their recurrence rule are exempt from the daily limit.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    #[getset(skip)]
+    exempt_scheduled_tasks: bool,
+    /// Whether pinned tasks are exempt from the daily limit.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    #[getset(skip)]
+    exempt_pinned_tasks: bool,
+    /// How many days each month a streak can miss without being broken.
+    #[serde(default, skip_serializing_if = "std::option::Option::is_none")]
+    streak_freezes: Option<u32>,
     #[serde(skip)]
     #[getset(skip)]
     // We want this to be a OnceCell just in case we pass the cut-off while running.
@@ -32,6 +68,7 @@ pub struct Config {
 
 impl Config {
     pub fn save(&self) -> Result<()> {
+        self.validate_requirements()?;
         let file = OpenOptions::new()
             .create(true)
             .truncate(true)
@@ -42,22 +79,93 @@ impl Config {
     }
 
     pub fn load() -> Result<Self> {
-        let mut config = if CONFIG_FILE_PATH.exists() {
+        if CONFIG_FILE_PATH.exists() {
             let data = fs::read(&*CONFIG_FILE_PATH)?;
-            serde_norway::from_slice(&data)?
+            Self::from_slice(&data)
         } else {
             let config = Self::default();
             config.save()?;
-            config
-        };
+            Ok(config)
+        }
+    }
+
+    /// Parses a config from YAML.
+    pub fn from_slice(data: &[u8]) -> Result<Self> {
+        let mut config: Self = serde_norway::from_slice(data)?;
         config.tasks_map = config
             .tasks
             .iter()
             .map(|g| (String::from(g.borrow().slug()), RcCell::clone(g)))
             .collect();
+        config.validate_requirements()?;
+        if let Some(staleness) = config.staleness {
+            staleness.validate()?;
+        }
+        if let Some(adaptive) = config.adaptive {
+            adaptive.validate()?;
+        }
         Ok(config)
     }
 
+    /// Makes sure that every task a task requires exists, and that no tasks require each other.
+    pub fn validate_requirements(&self) -> Result<()> {
+        for task in self.tasks.iter() {
+            let task = task.borrow();
+            if let Some(missing) = task.requires.keys().find(|r| !self.contains_task(r)) {
+                return Err(Error::missing_requirement(task.slug(), missing));
+            }
+        }
+        let mut checked = HashSet::new();
+        for slug in self.task_slugs() {
+            let mut path = Vec::new();
+            self.check_requirement_cycles(slug, &mut path, &mut checked)?;
+        }
+        Ok(())
+    }
+
+    /// Returns the first task that requires the given task, ignoring any of the given tasks.
+    pub fn required_by<S: AsRef<str>>(
+        &self,
+        slug: S,
+        ignoring: &BTreeSet<String>,
+    ) -> Option<String> {
+        self.tasks
+            .iter()
+            .map(|t| t.borrow())
+            .find(|t| !ignoring.contains(t.slug()) && t.requires.contains_key(slug.as_ref()))
+            .map(|t| String::from(t.slug()))
+    }
+
+    /// Does a depth-first search of a task's requirements, returning an error if it leads back to
+    /// any task in `path`.
+    fn check_requirement_cycles(
+        &self,
+        slug: String,
+        path: &mut Vec<String>,
+        checked: &mut HashSet<String>,
+    ) -> Result<()> {
+        if let Some(pos) = path.iter().position(|s| *s == slug) {
+            let mut cycle = path.split_off(pos);
+            cycle.push(slug);
+            return Err(Error::requirement_cycle(cycle));
+        }
+        if checked.contains(&slug) {
+            return Ok(());
+        }
+        let requires: Vec<_> = self
+            .get_task(&slug)
+            .map(|t| t.borrow().requires.keys().cloned().collect())
+            .unwrap_or_default();
+        path.push(slug);
+        for required in requires {
+            self.check_requirement_cycles(required, path, checked)?;
+        }
+        if let Some(slug) = path.pop() {
+            checked.insert(slug);
+        }
+        Ok(())
+    }
+
     pub(crate) fn add_task(&mut self, task: RcCell<TaskConfig>) -> Result<()> {
         let slug = String::from(task.borrow().slug());
         if self.contains_task(&slug) {
@@ -85,6 +193,45 @@ impl Config {
             .collect()
     }
 
+    #[inline]
+    pub fn exempt_scheduled_tasks(&self) -> bool {
+        self.exempt_scheduled_tasks
+    }
+
+    #[inline]
+    pub fn exempt_pinned_tasks(&self) -> bool {
+        self.exempt_pinned_tasks
+    }
+
+    /// Returns the names of all the exclusion groups a task belongs to.
+    pub fn exclusion_groups_of<S: AsRef<str>>(&self, slug: S) -> BTreeSet<String> {
+        let slug = slug.as_ref();
+        let mut groups: BTreeSet<_> = self
+            .get_task(slug)
+            .map(|t| t.borrow().exclusion_groups.iter().cloned().collect())
+            .unwrap_or_default();
+        groups.extend(
+            self.exclusion_groups
+                .iter()
+                .filter(|(_, members)| members.iter().any(|m| m == slug))
+                .map(|(name, _)| name.clone()),
+        );
+        groups
+    }
+
+    /// Returns `true` if the two tasks share an exclusion group.
+    pub fn mutually_exclusive<S1: AsRef<str>, S2: AsRef<str>>(&self, slug1: S1, slug2: S2) -> bool {
+        let groups = self.exclusion_groups_of(slug1);
+        !groups.is_empty() && !groups.is_disjoint(&self.exclusion_groups_of(slug2))
+    }
+
+    /// Fixes what today's date is considered to be, for testing.
+    #[cfg(test)]
+    pub(crate) fn with_today(self, date: Date) -> Self {
+        let _ = self.effective_date.set(date);
+        self
+    }
+
     /// What today's date should be considered, taken the config's cut-off time.
     pub fn today(&self) -> Date {
         *self
@@ -92,6 +239,28 @@ impl Config {
             .get_or_init(|| now_with_cutoff(self.cut_off))
     }
 
+    /// The limit to use on the given date. This will be the limit scheduled for the date's weekday,
+    /// falling back to the schedule's default and then `limit-by`.
+    pub fn limit_by_on(&self, date: Date) -> &LimitTasksBy {
+        self.limit_schedule
+            .as_ref()
+            .and_then(|s| s.get(date.weekday()))
+            .unwrap_or(&self.limit_by)
+    }
+
+    /// The limit to use for today, taking the config's cut-off time into account.
+    #[inline]
+    pub fn todays_limit_by(&self) -> &LimitTasksBy {
+        self.limit_by_on(self.today())
+    }
+
+    /// The seed to pick tasks with on the given date, if the config has a seed secret.
+    pub fn seed_on(&self, date: Date) -> Option<u64> {
+        self.seed_secret
+            .as_ref()
+            .map(|secret| stable_hash(format!("{secret}:{date}")))
+    }
+
     pub fn date_with_cutoff(&self, date: Date) -> OffsetDateTime {
         let offset = UtcOffset::current_local_offset().unwrap();
         OffsetDateTime::new_in_offset(date, self.cut_off, offset)
@@ -117,6 +286,16 @@ impl Default for Config {
             cut_off: *DEFAULT_CUT_OFF,
             effective_date: OnceCell::new(),
             limit_by: LimitTasksBy::Tasks { tasks: 1 },
+            limit_schedule: None,
+            seed_secret: None,
+            strategy: PickStrategy::default(),
+            staleness: None,
+            adaptive: None,
+            tag_quotas: BTreeMap::new(),
+            exclusion_groups: BTreeMap::new(),
+            exempt_scheduled_tasks: false,
+            exempt_pinned_tasks: false,
+            streak_freezes: None,
         };
         // Populate what today is ASAP
         let _ = config.today();
@@ -124,11 +303,266 @@ impl Default for Config {
     }
 }
 
+/// How many tasks are picked each day. Every limit given is honoured together, and `min-tasks`
+/// allows picking tasks even if doing so goes over the spoon budget.
 #[derive(Debug, Clone, Serialize, Deserialize, EnumIs, PartialEq)]
-#[serde(untagged)]
+#[serde(
+    untagged,
+    rename_all_fields = "kebab-case",
+    try_from = "LimitTasksByFields"
+)]
 pub enum LimitTasksBy {
-    Tasks { tasks: usize },
-    Spoons { spoons: u16 },
+    TasksAndSpoons {
+        tasks: usize,
+        spoons: u16,
+        #[serde(default, skip_serializing_if = "std::option::Option::is_none")]
+        min_tasks: Option<usize>,
+    },
+    Tasks {
+        tasks: usize,
+    },
+    Spoons {
+        spoons: u16,
+        #[serde(default, skip_serializing_if = "std::option::Option::is_none")]
+        min_tasks: Option<usize>,
+    },
+}
+
+/// The fields a limit can be given with, so that unknown fields and `min-tasks` without a spoon
+/// budget are rejected instead of being silently dropped.
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+struct LimitTasksByFields {
+    tasks: Option<usize>,
+    spoons: Option<u16>,
+    min_tasks: Option<usize>,
+}
+
+impl TryFrom<LimitTasksByFields> for LimitTasksBy {
+    type Error = Error;
+
+    fn try_from(value: LimitTasksByFields) -> Result<Self> {
+        let LimitTasksByFields {
+            tasks,
+            spoons,
+            min_tasks,
+        } = value;
+        match (tasks, spoons, min_tasks) {
+            (Some(tasks), Some(spoons), min_tasks) => Ok(Self::TasksAndSpoons {
+                tasks,
+                spoons,
+                min_tasks,
+            }),
+            (Some(tasks), None, None) => Ok(Self::Tasks { tasks }),
+            (None, Some(spoons), min_tasks) => Ok(Self::Spoons { spoons, min_tasks }),
+            (Some(_), None, Some(_)) => Err(Error::simple(
+                "min-tasks only applies when there's a spoon budget",
+            )),
+            (None, None, _) => Err(Error::simple("A limit needs tasks, spoons, or both")),
+        }
+    }
+}
+
+impl LimitTasksBy {
+    /// The maximum number of tasks to pick, if any.
+    pub fn max_tasks(&self) -> Option<usize> {
+        match self {
+            Self::TasksAndSpoons { tasks, .. } | Self::Tasks { tasks } => Some(*tasks),
+            Self::Spoons { .. } => None,
+        }
+    }
+
+    /// The maximum number of spoons today's tasks can add up to, if any.
+    pub fn max_spoons(&self) -> Option<u16> {
+        match self {
+            Self::TasksAndSpoons { spoons, .. } | Self::Spoons { spoons, .. } => Some(*spoons),
+            Self::Tasks { .. } => None,
+        }
+    }
+
+    /// The number of tasks that should be picked even if they go over the spoon budget.
+    pub fn min_tasks(&self) -> usize {
+        match self {
+            Self::TasksAndSpoons { min_tasks, .. } | Self::Spoons { min_tasks, .. } => {
+                min_tasks.unwrap_or(0)
+            }
+            Self::Tasks { .. } => 0,
+        }
+    }
+}
+
+/// How much a task's weight is multiplied by, based on how many days it's been since the task was
+/// last chosen.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, EnumIs, PartialEq)]
+#[serde(tag = "curve", rename_all = "kebab-case")]
+pub enum StalenessBoost {
+    /// Multiplies by `1 + rate * days`.
+    Linear { rate: f64 },
+    /// Multiplies by `(1 + rate) ^ days`.
+    Exponential { rate: f64 },
+    /// Multiplies by `1 + rate * days`, but never by more than `max`.
+    Capped { rate: f64, max: f64 },
+}
+
+impl StalenessBoost {
+    /// The largest a boost can be, so that weights never become infinite.
+    pub const MAX_BOOST: f64 = 1_000_000.0;
+
+    /// Returns what a task's weight should be multiplied by if it was last chosen the given number
+    /// of days ago.
+    pub fn multiplier(&self, days: i64) -> f64 {
+        let days = days.max(0) as f64;
+        let boost = match self {
+            Self::Linear { rate } => 1.0 + rate * days,
+            Self::Exponential { rate } => (1.0 + rate).powf(days),
+            Self::Capped { rate, max } => (1.0 + rate * days).min(*max),
+        };
+        boost.clamp(0.0, Self::MAX_BOOST)
+    }
+
+    /// Returns what the weight of a task that has never been chosen should be multiplied by. These
+    /// get the full boost if the curve has a maximum, and no boost otherwise, since an unbounded
+    /// boost would make new tasks crowd out ones that have gone unpicked for a long time.
+    pub fn unchosen_multiplier(&self) -> f64 {
+        match self {
+            Self::Capped { max, .. } => max.clamp(0.0, Self::MAX_BOOST),
+            Self::Linear { .. } | Self::Exponential { .. } => 1.0,
+        }
+    }
+
+    /// Makes sure that the boost can never bring a task's weight down to 0, which would stop it
+    /// from being picked.
+    pub fn validate(&self) -> Result<()> {
+        let (Self::Linear { rate } | Self::Exponential { rate } | Self::Capped { rate, .. }) = self;
+        if rate.is_nan() || *rate < 0.0 {
+            return Err(Error::simple(format!(
+                "The staleness rate must be 0 or more, not {rate}."
+            )));
+        }
+        if let Self::Capped { max, .. } = self
+            && (max.is_nan() || *max < 1.0)
+        {
+            return Err(Error::simple(format!(
+                "The staleness max must be 1 or more, not {max}."
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// How many tasks with a given tag can be picked each day.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub struct TagQuota {
+    #[serde(default, skip_serializing_if = "std::option::Option::is_none")]
+    pub min: Option<usize>,
+    #[serde(default, skip_serializing_if = "std::option::Option::is_none")]
+    pub max: Option<usize>,
+}
+
+pub const DEFAULT_ADAPTIVE_DECAY: f64 = 0.9;
+
+#[inline(always)]
+fn default_adaptive_decay() -> f64 {
+    DEFAULT_ADAPTIVE_DECAY
+}
+
+#[inline(always)]
+fn default_adaptive_min() -> f64 {
+    0.25
+}
+
+#[inline(always)]
+fn default_adaptive_max() -> f64 {
+    1.5
+}
+
+/// Settings for learning tasks' weights from how often they're completed. A task's weight is
+/// multiplied by somewhere between `min` (never completed) and `max` (always completed).
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub struct AdaptiveWeights {
+    /// How much older behaviour counts each time a task is chosen, between 0 and 1. Lower values
+    /// forget older behaviour faster.
+    #[serde(default = "default_adaptive_decay")]
+    pub decay: f64,
+    #[serde(default = "default_adaptive_min")]
+    pub min: f64,
+    #[serde(default = "default_adaptive_max")]
+    pub max: f64,
+}
+
+impl AdaptiveWeights {
+    /// Returns what a task's weight should be multiplied by, given its completion rate.
+    pub fn multiplier(&self, completion_rate: f64) -> f64 {
+        self.min + (self.max - self.min) * completion_rate.clamp(0.0, 1.0)
+    }
+
+    /// Makes sure that the multiplier can never bring a task's weight down to 0 or below, which
+    /// would stop it from being picked.
+    pub fn validate(&self) -> Result<()> {
+        let Self { decay, min, max } = *self;
+        if !(decay.is_finite() && (0.0..=1.0).contains(&decay)) {
+            return Err(Error::simple(format!(
+                "The adaptive decay must be between 0 and 1, not {decay}."
+            )));
+        }
+        if !(min.is_finite() && max.is_finite() && 0.0 < min && min <= max) {
+            return Err(Error::simple(format!(
+                "The adaptive min must be more than 0 and no more than the max, not {min} and {max}."
+            )));
+        }
+        Ok(())
+    }
+}
+
+impl Default for AdaptiveWeights {
+    fn default() -> Self {
+        Self {
+            decay: DEFAULT_ADAPTIVE_DECAY,
+            min: default_adaptive_min(),
+            max: default_adaptive_max(),
+        }
+    }
+}
+
+/// Different limits for different days of the week. Any days without a limit use the `default`
+/// limit, if there is one, or the config's `limit-by` otherwise.
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct LimitSchedule {
+    #[serde(default, skip_serializing_if = "std::option::Option::is_none")]
+    pub monday: Option<LimitTasksBy>,
+    #[serde(default, skip_serializing_if = "std::option::Option::is_none")]
+    pub tuesday: Option<LimitTasksBy>,
+    #[serde(default, skip_serializing_if = "std::option::Option::is_none")]
+    pub wednesday: Option<LimitTasksBy>,
+    #[serde(default, skip_serializing_if = "std::option::Option::is_none")]
+    pub thursday: Option<LimitTasksBy>,
+    #[serde(default, skip_serializing_if = "std::option::Option::is_none")]
+    pub friday: Option<LimitTasksBy>,
+    #[serde(default, skip_serializing_if = "std::option::Option::is_none")]
+    pub saturday: Option<LimitTasksBy>,
+    #[serde(default, skip_serializing_if = "std::option::Option::is_none")]
+    pub sunday: Option<LimitTasksBy>,
+    #[serde(default, skip_serializing_if = "std::option::Option::is_none")]
+    pub default: Option<LimitTasksBy>,
+}
+
+impl LimitSchedule {
+    /// Returns the limit for the given weekday, or the schedule's default.
+    pub fn get(&self, weekday: Weekday) -> Option<&LimitTasksBy> {
+        let limit_by = match weekday {
+            Weekday::Monday => &self.monday,
+            Weekday::Tuesday => &self.tuesday,
+            Weekday::Wednesday => &self.wednesday,
+            Weekday::Thursday => &self.thursday,
+            Weekday::Friday => &self.friday,
+            Weekday::Saturday => &self.saturday,
+            Weekday::Sunday => &self.sunday,
+        };
+        limit_by.as_ref().or(self.default.as_ref())
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default, EnumIs, PartialEq)]
@@ -146,3 +580,133 @@ impl From<bool> for DisabledOptions {
         if value { Self::Disabled } else { Self::Enabled }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::RanddGoalsError;
+    use pretty_assertions::assert_eq;
+
+    fn limit(yaml: &str) -> Result<LimitTasksBy> {
+        Ok(serde_norway::from_str(yaml)?)
+    }
+
+    #[test]
+    fn parses_limits() {
+        assert_eq!(limit("tasks: 3").unwrap(), LimitTasksBy::Tasks { tasks: 3 });
+        assert_eq!(
+            limit("spoons: 10\nmin-tasks: 1").unwrap(),
+            LimitTasksBy::Spoons {
+                spoons: 10,
+                min_tasks: Some(1)
+            }
+        );
+        assert_eq!(
+            limit("tasks: 3\nspoons: 10").unwrap(),
+            LimitTasksBy::TasksAndSpoons {
+                tasks: 3,
+                spoons: 10,
+                min_tasks: None
+            }
+        );
+    }
+
+    #[test]
+    fn unchosen_tasks_are_only_boosted_up_to_a_cap() {
+        let capped = StalenessBoost::Capped {
+            rate: 0.5,
+            max: 4.0,
+        };
+        assert_eq!(capped.unchosen_multiplier(), 4.0);
+        assert_eq!(
+            StalenessBoost::Linear { rate: 0.5 }.unchosen_multiplier(),
+            1.0
+        );
+        assert_eq!(
+            StalenessBoost::Exponential { rate: 0.5 }.unchosen_multiplier(),
+            1.0
+        );
+    }
+
+    #[test]
+    fn validates_adaptive_weights() {
+        let adaptive = |yaml: &str| {
+            serde_norway::from_str::<AdaptiveWeights>(yaml)
+                .unwrap()
+                .validate()
+        };
+        for yaml in ["{}", "{min: 1.0, max: 1.0}", "{decay: 0.0}", "{decay: 1.0}"] {
+            assert!(adaptive(yaml).is_ok(), "{yaml} should be valid");
+        }
+        for yaml in [
+            "{min: 0.0}",
+            "{min: -1.0}",
+            "{min: 2.0, max: 1.0}",
+            "{max: .inf}",
+            "{decay: 1.5}",
+            "{decay: -0.1}",
+            "{decay: .nan}",
+        ] {
+            assert!(adaptive(yaml).is_err(), "{yaml} should be invalid");
+        }
+    }
+
+    /// Loads a config with the given tasks, given as pairs of a slug and the tasks it requires.
+    fn config_requiring(tasks: &[(&str, &[&str])]) -> Result<Config> {
+        let mut yaml = String::from("cut-off: 04:00:00.0\nlimit-by: { tasks: 3 }\ntasks:\n");
+        for (slug, requires) in tasks {
+            let requires: Vec<_> = requires.iter().map(|r| format!("{r}: 1")).collect();
+            yaml.push_str(&format!(
+                "- {{ slug: {slug}, task: {slug}, weight: 1.0, spoons: 1, requires: {{ {} }} }}\n",
+                requires.join(", ")
+            ));
+        }
+        Config::from_slice(yaml.as_bytes())
+    }
+
+    #[test]
+    fn accepts_valid_requirements() {
+        // b is required through two paths, which isn't a cycle.
+        let tasks: &[(&str, &[&str])] = &[("a", &["b", "c"]), ("b", &[]), ("c", &["b"])];
+        assert!(config_requiring(tasks).is_ok());
+    }
+
+    #[test]
+    fn rejects_missing_requirements() {
+        let err = config_requiring(&[("a", &["b"])]).unwrap_err();
+        assert!(matches!(
+            err,
+            Error::RanddGoals {
+                source: RanddGoalsError::MissingRequirement { ref slug, ref requires },
+                ..
+            } if slug == "a" && requires == "b"
+        ));
+    }
+
+    #[test]
+    fn rejects_requirement_cycles() {
+        let cycle = |tasks: &[(&str, &[&str])]| match config_requiring(tasks) {
+            Err(Error::RanddGoals {
+                source: RanddGoalsError::RequirementCycle { cycle },
+                ..
+            }) => cycle,
+            other => panic!("expected a requirement cycle, got {other:?}"),
+        };
+        assert_eq!(cycle(&[("a", &["a"])]), "a -> a");
+        assert_eq!(
+            cycle(&[("a", &["b"]), ("b", &["c"]), ("c", &["d"]), ("d", &["b"])]),
+            "b -> c -> d -> b"
+        );
+    }
+
+    #[test]
+    fn rejects_dropped_fields() {
+        for yaml in [
+            "tasks: 3\nmin-tasks: 1",
+            "tasks: 3\nspoon: 10",
+            "min-tasks: 1",
+        ] {
+            assert!(limit(yaml).is_err(), "{yaml:?} should be rejected");
+        }
+    }
+}
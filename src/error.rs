@@ -1,16 +1,19 @@
 use crate::task::TaskBuilderError;
 use camino::FromPathBufError as NonUtf8PathError;
 use csv::Error as CsvError;
-use notify_rust::error::Error as NotificationError;
 use pastey::paste;
 use rand::distr::weighted::Error as RandWeightError;
+use serde_json::Error as JsonError;
 use serde_norway::Error as YamlError;
 use snafu::{AsBacktrace, Backtrace, Snafu};
 use std::{io::Error as IoError, result::Result as BaseResult, string::FromUtf8Error};
 use time::{
-    error::IndeterminateOffset as IndeterminateOffsetError, error::Parse as DateParsingError,
+    Date, error::IndeterminateOffset as IndeterminateOffsetError, error::Parse as DateParsingError,
 };
 
+/// Boxed since it's much larger than the other errors.
+type NotificationError = Box<notify_rust::error::Error>;
+
 macro_rules! impl_error {
     ($($name:ident,)+) => {
         paste! {
@@ -19,6 +22,7 @@ macro_rules! impl_error {
             #[snafu(visibility(pub))]
             pub enum Error {
                 $(
+                    #[snafu(display("{source}"))]
                     $name {
                         source: [<$name Error>],
                         backtrace: Backtrace,
@@ -55,12 +59,19 @@ impl_error! {
     RandWeight,
     FromUtf8,
     Yaml,
+    Json,
     IndeterminateOffset,
     TaskBuilder,
     Csv,
     DateParsing,
 }
 
+impl From<notify_rust::error::Error> for Error {
+    fn from(error: notify_rust::error::Error) -> Self {
+        Self::from(Box::new(error))
+    }
+}
+
 impl Error {
     #[inline(always)]
     pub(crate) fn simple<S: AsRef<str>>(message: S) -> Self {
@@ -106,6 +117,94 @@ impl Error {
         }
     }
 
+    #[inline(always)]
+    pub(crate) fn missing_requirement<S1: AsRef<str>, S2: AsRef<str>>(
+        slug: S1,
+        requires: S2,
+    ) -> Self {
+        let source = RanddGoalsError::MissingRequirement {
+            slug: String::from(slug.as_ref()),
+            requires: String::from(requires.as_ref()),
+        };
+        Self::RanddGoals {
+            source,
+            backtrace: Backtrace::new(),
+        }
+    }
+
+    #[inline(always)]
+    pub(crate) fn task_is_required<S1: AsRef<str>, S2: AsRef<str>>(
+        slug: S1,
+        required_by: S2,
+    ) -> Self {
+        let source = RanddGoalsError::TaskIsRequired {
+            slug: String::from(slug.as_ref()),
+            required_by: String::from(required_by.as_ref()),
+        };
+        Self::RanddGoals {
+            source,
+            backtrace: Backtrace::new(),
+        }
+    }
+
+    #[inline(always)]
+    pub(crate) fn requirement_cycle<I, S>(slugs: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let source = RanddGoalsError::RequirementCycle {
+            cycle: slugs
+                .into_iter()
+                .map(|s| String::from(s.as_ref()))
+                .collect::<Vec<_>>()
+                .join(" -> "),
+        };
+        Self::RanddGoals {
+            source,
+            backtrace: Backtrace::new(),
+        }
+    }
+
+    #[inline(always)]
+    pub(crate) fn invalid_recurrence<S1: AsRef<str>, S2: AsRef<str>>(rule: S1, reason: S2) -> Self {
+        let source = RanddGoalsError::InvalidRecurrence {
+            rule: String::from(rule.as_ref()),
+            reason: String::from(reason.as_ref()),
+        };
+        Self::RanddGoals {
+            source,
+            backtrace: Backtrace::new(),
+        }
+    }
+
+    #[inline(always)]
+    pub(crate) fn checklist_item_not_found<S1: AsRef<str>, S2: AsRef<str>>(
+        slug: S1,
+        item: S2,
+    ) -> Self {
+        let source = RanddGoalsError::ChecklistItemNotFound {
+            slug: String::from(slug.as_ref()),
+            item: String::from(item.as_ref()),
+        };
+        Self::RanddGoals {
+            source,
+            backtrace: Backtrace::new(),
+        }
+    }
+
+    #[inline(always)]
+    pub(crate) fn task_not_picked_on<S: AsRef<str>>(slug: S, date: Date) -> Self {
+        let source = RanddGoalsError::TaskNotPickedOn {
+            slug: String::from(slug.as_ref()),
+            date,
+        };
+        Self::RanddGoals {
+            source,
+            backtrace: Backtrace::new(),
+        }
+    }
+
     #[inline(always)]
     pub(crate) fn task_state_not_loaded<S: AsRef<str>>(slug: S) -> Self {
         let source = RanddGoalsError::TaskStateNotLoaded {
@@ -134,6 +233,18 @@ pub enum RanddGoalsError {
     TaskNotFound { slug: String },
     #[snafu(display("The current state for the {slug} task has not be loaded."))]
     TaskStateNotLoaded { slug: String },
+    #[snafu(display("The task '{slug}' requires the task '{requires}', which doesn't exist."))]
+    MissingRequirement { slug: String, requires: String },
+    #[snafu(display("The task '{slug}' can't be removed because '{required_by}' requires it."))]
+    TaskIsRequired { slug: String, required_by: String },
+    #[snafu(display("Tasks can't require each other: {cycle}"))]
+    RequirementCycle { cycle: String },
+    #[snafu(display("Invalid recurrence rule '{rule}': {reason}."))]
+    InvalidRecurrence { rule: String, reason: String },
+    #[snafu(display("The task '{slug}' has no checklist item '{item}'."))]
+    ChecklistItemNotFound { slug: String, item: String },
+    #[snafu(display("The task '{slug}' wasn't one of the tasks on {date}."))]
+    TaskNotPickedOn { slug: String, date: Date },
     #[snafu(display("Files with the extension '{extension}' are not supported"))]
     UnsupportedFileType { extension: String },
     #[snafu(display("{message}"))]
@@ -1 +1,340 @@
+use crate::{HISTORY_FILE_PATH, Result, State, util::now};
+use camino::Utf8PathBuf;
+use file_rotate::{ContentLimit, FileRotate, compression::Compression, suffix::AppendCount};
+use serde::{Deserialize, Serialize};
+use serde_jsonlines::{JsonLinesReader, JsonLinesWriter};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs::File,
+    io::{self, BufReader},
+    iter,
+};
+use strum::EnumIs;
+use time::{Date, OffsetDateTime};
 
+/// How many events are written to the history file before it's rotated.
+pub const HISTORY_FILE_LINES: usize = 10_000;
+/// How many rotated history files are kept.
+pub const HISTORY_FILE_COUNT: usize = 100;
+
+/// Something that happened to a task.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, EnumIs)]
+#[serde(
+    tag = "event",
+    rename_all = "kebab-case",
+    rename_all_fields = "kebab-case"
+)]
+pub enum EventKind {
+    /// The task was picked as one of the day's tasks, or carried over from an earlier day if it
+    /// takes more than one day.
+    Generated {
+        task: String,
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        carried: bool,
+        /// The task's title with its placeholders filled in, if it's a template.
+        #[serde(default, skip_serializing_if = "std::option::Option::is_none")]
+        picked_as: Option<String>,
+        #[serde(default, skip_serializing_if = "std::option::Option::is_none")]
+        variant: Option<String>,
+    },
+    /// The task was completed.
+    Completed {
+        task: String,
+        #[serde(default, skip_serializing_if = "std::option::Option::is_none")]
+        variant: Option<String>,
+        #[serde(default, skip_serializing_if = "std::option::Option::is_none")]
+        amount: Option<f64>,
+        /// The task's title with its placeholders filled in, if it's a template.
+        #[serde(default, skip_serializing_if = "std::option::Option::is_none")]
+        completed_as: Option<String>,
+    },
+    /// The task was replaced with another task.
+    Refreshed {
+        task: String,
+    },
+    /// The task was removed when all of the day's tasks were replaced.
+    Reset {
+        task: String,
+    },
+    Disabled {
+        task: String,
+    },
+    Enabled {
+        task: String,
+    },
+    Added {
+        task: String,
+    },
+    Updated {
+        task: String,
+    },
+    Removed {
+        task: String,
+    },
+}
+
+impl EventKind {
+    /// The slug of the task the event happened to.
+    pub fn task(&self) -> &str {
+        match self {
+            Self::Generated { task, .. }
+            | Self::Completed { task, .. }
+            | Self::Refreshed { task }
+            | Self::Reset { task }
+            | Self::Disabled { task }
+            | Self::Enabled { task }
+            | Self::Added { task }
+            | Self::Updated { task }
+            | Self::Removed { task } => task,
+        }
+    }
+}
+
+/// An entry in the history file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Event {
+    /// When the event was recorded.
+    pub timestamp: OffsetDateTime,
+    /// The effective date the event was recorded on, taking the cut-off into account.
+    pub date: Date,
+    #[serde(flatten)]
+    pub kind: EventKind,
+}
+
+impl Event {
+    pub fn new(date: Date, kind: EventKind) -> Self {
+        Self {
+            timestamp: now(),
+            date,
+            kind,
+        }
+    }
+}
+
+/// How a task looked on a day it was part of.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PickedAs {
+    /// The task's title with its placeholders filled in, if it's a template.
+    pub title: Option<String>,
+    pub variant: Option<String>,
+}
+
+/// The tasks that were picked and completed on a single day.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DayRecord {
+    /// The tasks that were part of that day and weren't replaced, including any carried over.
+    pub tasks: BTreeSet<String>,
+    /// The tasks that were carried over from an earlier day, rather than picked that day.
+    pub carried: BTreeSet<String>,
+    pub completed: BTreeSet<String>,
+    /// How each of the day's tasks looked when it was picked or carried over.
+    pub picked_as: BTreeMap<String, PickedAs>,
+}
+
+impl DayRecord {
+    /// Returns `true` if the task was either picked or completed that day.
+    pub fn includes(&self, slug: &str) -> bool {
+        self.tasks.contains(slug) || self.completed.contains(slug)
+    }
+
+    fn add(&mut self, kind: &EventKind) {
+        match kind {
+            EventKind::Generated {
+                task,
+                carried,
+                picked_as,
+                variant,
+            } => {
+                self.tasks.insert(task.clone());
+                if *carried {
+                    self.carried.insert(task.clone());
+                }
+                self.picked_as.insert(
+                    task.clone(),
+                    PickedAs {
+                        title: picked_as.clone(),
+                        variant: variant.clone(),
+                    },
+                );
+            }
+            EventKind::Refreshed { task } | EventKind::Reset { task } => {
+                self.tasks.remove(task);
+                self.carried.remove(task);
+                self.picked_as.remove(task);
+            }
+            EventKind::Completed { task, .. } => {
+                self.completed.insert(task.clone());
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Groups events by the date they were recorded on.
+pub fn days<'a, I>(events: I) -> BTreeMap<Date, DayRecord>
+where
+    I: IntoIterator<Item = &'a Event>,
+{
+    let mut days: BTreeMap<Date, DayRecord> = BTreeMap::new();
+    for event in events {
+        days.entry(event.date).or_default().add(&event.kind);
+    }
+    days
+}
+
+/// Reads the history's record for a single day.
+pub fn read_day(date: Date) -> Result<DayRecord> {
+    let events = read_since(Some(date))?;
+    Ok(days(events.iter().filter(|e| e.date == date))
+        .remove(&date)
+        .unwrap_or_default())
+}
+
+/// Groups events by the date they were recorded on, including a record for today that also
+/// takes today's tasks from the state into account in case the history missed any of them.
+pub fn days_until_today<'a, I>(events: I, state: &State) -> BTreeMap<Date, DayRecord>
+where
+    I: IntoIterator<Item = &'a Event>,
+{
+    let today = state.todays_date();
+    let generated_today = state.last_generated_date() == today;
+    let mut days = days(events);
+    let record = days.entry(today).or_default();
+    for slug in state.todays_tasks() {
+        let Some(task) = state.get_task(slug) else {
+            continue;
+        };
+        if task.last_chosen().is_some_and(|d| d == today) {
+            record.tasks.insert(slug.clone());
+        } else if generated_today {
+            record.tasks.insert(slug.clone());
+            record.carried.insert(slug.clone());
+        }
+        if task.completed() {
+            record.completed.insert(slug.clone());
+        }
+    }
+    days
+}
+
+fn history_file() -> FileRotate<AppendCount> {
+    FileRotate::new(
+        &*HISTORY_FILE_PATH,
+        AppendCount::new(HISTORY_FILE_COUNT),
+        ContentLimit::Lines(HISTORY_FILE_LINES),
+        Compression::None,
+        None,
+    )
+}
+
+/// Appends events to the history file, rotating it if needed.
+pub fn append<I>(events: I) -> Result<()>
+where
+    I: IntoIterator<Item = Event>,
+{
+    let mut events = events.into_iter().peekable();
+    if events.peek().is_none() {
+        return Ok(());
+    }
+    let mut writer = JsonLinesWriter::new(history_file());
+    writer.write_all(events)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// The history file followed by its rotated files, from newest to oldest.
+fn history_paths() -> Vec<Utf8PathBuf> {
+    iter::once(HISTORY_FILE_PATH.clone())
+        .chain((1..=HISTORY_FILE_COUNT).map(|n| format!("{}.{n}", *HISTORY_FILE_PATH).into()))
+        .collect()
+}
+
+/// Reads the events recorded on or after a date, or every event if there isn't one, from oldest to
+/// newest. Rotated files that were finished before the date aren't read at all.
+pub fn read_since(since: Option<Date>) -> Result<Vec<Event>> {
+    read_files_since(history_paths(), since)
+}
+
+/// Reads the events on or after a date from history files given from newest to oldest.
+fn read_files_since<I>(paths: I, since: Option<Date>) -> Result<Vec<Event>>
+where
+    I: IntoIterator<Item = Utf8PathBuf>,
+{
+    let mut files = Vec::new();
+    for path in paths.into_iter().filter(|p| p.exists()) {
+        let reader = JsonLinesReader::new(BufReader::new(File::open(path)?));
+        let events = reader.read_all::<Event>().collect::<io::Result<Vec<_>>>()?;
+        // Events are only ever recorded on or before the day they happen, so once a file starts
+        // before the date, every older file does too.
+        let done = since.is_some_and(|since| {
+            events
+                .first()
+                .is_some_and(|event| event.timestamp.date() < since)
+        });
+        files.push(events);
+        if done {
+            break;
+        }
+    }
+    Ok(files
+        .into_iter()
+        .rev()
+        .flatten()
+        .filter(|event| since.is_none_or(|since| event.date >= since))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::fs;
+    use time::macros::{date, datetime};
+
+    fn event(timestamp: OffsetDateTime, date: Date) -> Event {
+        Event {
+            timestamp,
+            date,
+            kind: EventKind::Generated {
+                task: String::from("a"),
+                carried: false,
+                picked_as: None,
+                variant: None,
+            },
+        }
+    }
+
+    fn write(path: &Utf8PathBuf, events: &[Event]) {
+        let mut writer = JsonLinesWriter::new(File::create(path).unwrap());
+        writer.write_all(events).unwrap();
+        writer.flush().unwrap();
+    }
+
+    #[test]
+    fn stops_reading_at_older_files() {
+        let dir = Utf8PathBuf::try_from(std::env::temp_dir())
+            .unwrap()
+            .join(format!("randd-tasks-history-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let paths: Vec<_> = (0..3).map(|n| dir.join(format!("history.{n}"))).collect();
+        let old = event(datetime!(2026-10-01 12:00 UTC), date!(2026 - 10 - 01));
+        let recent = event(datetime!(2026-10-10 12:00 UTC), date!(2026 - 10 - 10));
+        let backdated = event(datetime!(2026-10-12 12:00 UTC), date!(2026 - 10 - 02));
+        let newest = event(datetime!(2026-10-12 12:00 UTC), date!(2026 - 10 - 12));
+        write(&paths[0], &[backdated.clone(), newest.clone()]);
+        write(&paths[1], &[old, recent.clone()]);
+        // This file is never reached, so it doesn't matter that it isn't valid.
+        fs::write(&paths[2], "not an event").unwrap();
+
+        let since = |date| read_files_since(paths.clone(), Some(date)).unwrap();
+        assert_eq!(
+            since(date!(2026 - 10 - 02)),
+            [recent, backdated, newest.clone()]
+        );
+        assert_eq!(since(date!(2026 - 10 - 12)), [newest]);
+        assert!(read_files_since(paths.clone(), None).is_err());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -17,9 +17,13 @@ pub(crate) mod error;
 pub use error::{Error, Result};
 pub mod util;
 pub use util::RcCell;
-mod picker;
+pub mod history;
+pub mod picker;
+pub use picker::Picker;
 pub(crate) use picker::*;
 pub mod serializers;
+pub mod stats;
+pub mod streak;
 
 lazy_static! {
     pub static ref CONFIG_FILE_PATH: Utf8PathBuf = {
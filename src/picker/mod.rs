@@ -0,0 +1,600 @@
+use crate::{
+    Error, Result, Task,
+    config::{LimitTasksBy, TagQuota},
+    history::EventKind,
+    state::State,
+};
+use rand::{RngCore, SeedableRng as _};
+use rand_chacha::ChaCha8Rng;
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+};
+use strum::EnumIs;
+
+mod strategies;
+pub use strategies::*;
+
+/// A strategy for choosing which task to pick next.
+pub trait Picker {
+    /// Chooses one of the candidate tasks. `candidates` are the tasks that can currently be chosen,
+    /// sorted by slug, and are never empty.
+    fn pick<'a>(
+        &mut self,
+        candidates: &[&'a Task],
+        state: &State,
+        rng: &mut dyn RngCore,
+    ) -> Result<&'a Task>;
+
+    /// Called after a task has been picked and added to today's tasks.
+    fn picked(&mut self, slug: &str, state: &mut State) {}
+}
+
+/// Why picking today's tasks stopped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIs)]
+pub enum LimitReached {
+    /// Today's tasks reached the maximum number of tasks.
+    Tasks(usize),
+    /// None of the remaining tasks fit within the spoon budget.
+    Spoons(u16),
+    /// There were no more tasks that could be chosen.
+    NoChoosableTasks,
+}
+
+impl fmt::Display for LimitReached {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Tasks(tasks) => write!(f, "reached the limit of {tasks} task(s)"),
+            Self::Spoons(spoons) => write!(f, "no more tasks fit within {spoons} spoon(s)"),
+            Self::NoChoosableTasks => write!(f, "no more tasks can be chosen"),
+        }
+    }
+}
+
+/// A tag whose minimum quota couldn't be met by today's tasks.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnmetQuota {
+    pub tag: String,
+    /// How many of today's tasks have the tag.
+    pub picked: usize,
+    /// How many of today's tasks should have the tag.
+    pub min: usize,
+}
+
+impl fmt::Display for UnmetQuota {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "only {} of the required {} '{}' task(s) could be picked",
+            self.picked, self.min, self.tag
+        )
+    }
+}
+
+/// The result of picking today's tasks.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PickOutcome {
+    /// The slugs of any newly picked tasks.
+    pub new_tasks: Vec<String>,
+    /// The limit that stopped any more tasks from being picked.
+    pub limited_by: LimitReached,
+    /// Any tag quotas that today's tasks don't meet.
+    pub unmet_quotas: Vec<UnmetQuota>,
+    /// Whether the previous day's tasks were cleared because it's a new day.
+    pub new_day: bool,
+}
+
+impl PickOutcome {
+    /// Returns `true` if any new tasks were picked or the previous day's tasks were cleared.
+    #[inline]
+    pub fn changed(&self) -> bool {
+        self.new_day || !self.new_tasks.is_empty()
+    }
+}
+
+/// Returns today's tasks that count towards today's limit.
+fn counted_tasks(state: &State) -> Vec<&Task> {
+    state
+        .todays_tasks()
+        .iter()
+        .filter_map(|s| state.get_task(s))
+        .filter(|t| !t.exempt_from_limit(state))
+        .collect()
+}
+
+/// Counts the spoons of today's tasks that count towards today's limit.
+fn counted_spoons(state: &State) -> u16 {
+    counted_tasks(state)
+        .iter()
+        .map(|t| t.todays_spoons(state))
+        .sum()
+}
+
+/// Counts how many of today's tasks have each tag.
+fn todays_tag_counts(state: &State) -> HashMap<String, usize> {
+    let mut counts = HashMap::new();
+    for task in state
+        .todays_tasks()
+        .iter()
+        .filter_map(|s| state.get_task(s))
+    {
+        for tag in task.tags() {
+            *counts.entry(tag).or_default() += 1;
+        }
+    }
+    counts
+}
+
+/// Returns `true` if picking the task would go over the maximum quota of any of its tags.
+fn exceeds_tag_quotas(
+    task: &Task,
+    quotas: &BTreeMap<String, TagQuota>,
+    counts: &HashMap<String, usize>,
+) -> bool {
+    task.tags().iter().any(|tag| {
+        quotas
+            .get(tag)
+            .and_then(|q| q.max)
+            .is_some_and(|max| counts.get(tag).copied().unwrap_or(0) >= max)
+    })
+}
+
+/// Returns the tags whose minimum quota hasn't been met yet, along with how many of today's tasks
+/// have them.
+fn unmet_tag_quotas(
+    quotas: &BTreeMap<String, TagQuota>,
+    counts: &HashMap<String, usize>,
+) -> Vec<UnmetQuota> {
+    quotas
+        .iter()
+        .filter_map(|(tag, quota)| {
+            let picked = counts.get(tag).copied().unwrap_or(0);
+            quota.min.filter(|min| picked < *min).map(|min| UnmetQuota {
+                tag: tag.clone(),
+                picked,
+                min,
+            })
+        })
+        .collect()
+}
+
+/// Creates the random number generator to pick tasks with. If the state has a seed, then the
+/// generator is derived from it and how many times it has already been used. ChaCha is used since,
+/// unlike `SmallRng`, its output is the same on every platform and version of `rand`.
+fn new_rng(state: &State) -> ChaCha8Rng {
+    if let Some(seed) = state.seed() {
+        ChaCha8Rng::seed_from_u64(seed.wrapping_add(state.rounds() as u64))
+    } else {
+        ChaCha8Rng::from_os_rng()
+    }
+}
+
+/// Updates a task that was just added to today's tasks. This is done straight away so that the
+/// task counts as picked today for the rest of the picking.
+fn choose(slug: &str, state: &State) {
+    if let Some(task) = state.get_task(slug) {
+        task.choose(state);
+    }
+}
+
+/// Picks tasks from the chooseable tasks using the given picker, one at a time, until one of the
+/// limits is reached. Each picked task is added to today's tasks _and_ updated.
+pub fn pick_tasks(
+    limit_by: &LimitTasksBy,
+    picker: &mut dyn Picker,
+    state: &mut State,
+) -> Result<PickOutcome> {
+    let mut rng = new_rng(state);
+    let mut new_tasks = Vec::new();
+    let quotas = state.config().tag_quotas().clone();
+    // Tasks that are pinned or scheduled for today are added before any other tasks are picked.
+    // Multi-day tasks carried over from earlier days don't count as having picked tasks today.
+    let picked_today = state
+        .todays_tasks()
+        .iter()
+        .filter_map(|s| state.get_task(s))
+        .any(|t| t.last_chosen() == Some(state.todays_date()));
+    if !picked_today {
+        let forced: Vec<_> = state
+            .tasks()
+            .into_iter()
+            .filter(|t| t.forced_on(state.todays_date()))
+            .map(|t| String::from(t.slug()))
+            .collect();
+        for slug in forced {
+            // Checked one at a time in case forced tasks exclude each other.
+            if let Some(task) = state.get_task(&slug)
+                && task.choosable(state)
+            {
+                log::debug!("Adding forced task {slug}");
+                task.pick_variant(None, &mut rng)?;
+                state.todays_tasks_mut().insert(slug.clone());
+                choose(&slug, state);
+                new_tasks.push(slug);
+            }
+        }
+    }
+    let limited_by = loop {
+        let num_tasks = counted_tasks(state).len();
+        if let Some(max_tasks) = limit_by.max_tasks()
+            && num_tasks >= max_tasks
+        {
+            break LimitReached::Tasks(max_tasks);
+        }
+        let tag_counts = todays_tag_counts(state);
+        let choosable: Vec<_> = state
+            .tasks()
+            .into_iter()
+            .filter(|t| t.choosable(state) && !exceeds_tag_quotas(t, &quotas, &tag_counts))
+            .collect();
+        if choosable.is_empty() {
+            break LimitReached::NoChoosableTasks;
+        }
+        // Still pick tasks if we haven't reached the minimum number of tasks, even if it goes
+        // over budget.
+        let remaining = limit_by
+            .max_spoons()
+            .filter(|_| num_tasks >= limit_by.min_tasks())
+            .map(|max_spoons| max_spoons.saturating_sub(counted_spoons(state)));
+        let tasks: Vec<_> = match remaining {
+            Some(remaining) => choosable
+                .into_iter()
+                .filter(|t| t.min_spoons() <= remaining)
+                .collect(),
+            None => choosable,
+        };
+        if tasks.is_empty() {
+            break LimitReached::Spoons(limit_by.max_spoons().unwrap_or_default());
+        }
+        // Prefer tasks with tags that haven't met their minimum quota yet.
+        let unmet = unmet_tag_quotas(&quotas, &tag_counts);
+        let required: Vec<_> = tasks
+            .iter()
+            .filter(|t| {
+                t.tags()
+                    .iter()
+                    .any(|tag| unmet.iter().any(|u| &u.tag == tag))
+            })
+            .copied()
+            .collect();
+        let tasks = if required.is_empty() { tasks } else { required };
+        let task = picker.pick(&tasks, state, &mut rng)?;
+        task.pick_variant(remaining, &mut rng)?;
+        let slug = String::from(task.slug());
+        log::debug!("Picked {slug}");
+        state.todays_tasks_mut().insert(slug.clone());
+        choose(&slug, state);
+        state.set_last_picked(&slug);
+        picker.picked(&slug, state);
+        new_tasks.push(slug);
+    };
+    for slug in new_tasks.iter() {
+        if let Some(task) = state.get_task(slug) {
+            task.roll(&mut rng);
+            state.record_generated(task, false);
+        }
+    }
+    let unmet_quotas = unmet_tag_quotas(&quotas, &todays_tag_counts(state));
+    for unmet in unmet_quotas.iter() {
+        log::warn!("Tag quota not met: {unmet}");
+    }
+    Ok(PickOutcome {
+        new_tasks,
+        limited_by,
+        unmet_quotas,
+        new_day: false,
+    })
+}
+
+/// Chooses new variants for some of today's tasks, keeping the tasks themselves. If a seed is
+/// given, it's used instead of the configured one.
+pub fn reroll_variants<I, S>(state: &mut State, slugs: I, seed: Option<u64>) -> Result<()>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    if seed.is_some() {
+        state.set_seed(seed);
+    }
+    let mut rng = new_rng(state);
+    let max_spoons = state.limit_by().max_spoons();
+    for slug in slugs {
+        let slug = slug.as_ref();
+        let task = state
+            .get_task(slug)
+            .filter(|_| state.todays_tasks().contains(slug))
+            .ok_or_else(|| Error::task_not_found(slug))?;
+        // The task's current variant is replaced, so its spoons are available again.
+        let remaining = max_spoons
+            .filter(|_| !task.exempt_from_limit(state))
+            .map(|m| {
+                m.saturating_sub(counted_spoons(state))
+                    .saturating_add(task.todays_spoons(state))
+            });
+        task.pick_variant(remaining, &mut rng)?;
+    }
+    state.next_round();
+    Ok(())
+}
+
+/// Picks todays tasks, if needed, using the configured strategy. If a seed is given, it's used
+/// instead of the configured one.
+pub fn pick_todays_tasks(state: &mut State, seed: Option<u64>) -> Result<PickOutcome> {
+    let mut picker = state.config().strategy().picker();
+    pick_todays_tasks_with(state, seed, picker.as_mut())
+}
+
+/// Picks todays tasks, if needed, using the given picker. If a seed is given, it's used instead of
+/// the configured one.
+pub fn pick_todays_tasks_with(
+    state: &mut State,
+    seed: Option<u64>,
+    picker: &mut dyn Picker,
+) -> Result<PickOutcome> {
+    let new_day = state.todays_date() > state.last_generated_date();
+    if new_day {
+        let carried: Vec<_> = state
+            .todays_tasks()
+            .iter()
+            .filter(|s| state.get_task(s).is_some_and(|t| t.carries_over(state)))
+            .cloned()
+            .collect();
+        for task in carried.iter().filter_map(|s| state.get_task(s)) {
+            state.record_generated(task, true);
+        }
+        state.todays_tasks_mut().clear();
+        state.todays_tasks_mut().extend(carried);
+        state.set_seed(state.config().seed_on(state.todays_date()));
+    }
+    if seed.is_some() {
+        state.set_seed(seed);
+    }
+    let limit_by = state.limit_by().clone();
+    let outcome = PickOutcome {
+        new_day,
+        ..pick_tasks(&limit_by, picker, state)?
+    };
+    if outcome.changed() {
+        log::debug!("Picked {} new task(s)", outcome.new_tasks.len());
+        state.mark_generated();
+        state.next_round();
+    } else {
+        log::debug!("No new tasks to pick.");
+    }
+    log::debug!("Stopped picking tasks: {}", outcome.limited_by);
+    Ok(outcome)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use time::Duration;
+
+    const CONFIG: &str = r#"
+cut-off: 04:00:00.0
+limit-by:
+  tasks: 3
+tasks:
+- { slug: a, task: A, weight: 1.0, spoons: 1 }
+- { slug: b, task: B, weight: 2.0, spoons: 1 }
+- { slug: c, task: C, weight: 3.0, spoons: 1 }
+- { slug: d, task: D, weight: 4.0, spoons: 1 }
+- { slug: e, task: E, weight: 5.0, spoons: 1 }
+- { slug: f, task: F, weight: 6.0, spoons: 1 }
+"#;
+
+    fn pick_with_seed(seed: u64) -> Vec<String> {
+        let mut state = State::from_config_yaml(CONFIG);
+        let outcome =
+            pick_todays_tasks_with(&mut state, Some(seed), &mut WeightedRandomPicker).unwrap();
+        outcome.new_tasks
+    }
+
+    #[test]
+    fn seeded_picks_are_pinned() {
+        assert_eq!(pick_with_seed(42), vec!["e", "f", "c"]);
+        assert_eq!(pick_with_seed(7), vec!["c", "d", "f"]);
+    }
+
+    #[test]
+    fn seeded_picks_are_reproducible() {
+        for seed in 0..20 {
+            assert_eq!(pick_with_seed(seed), pick_with_seed(seed));
+        }
+    }
+
+    /// Creates a state with the given limit and tasks, given as `slug: spoons` pairs.
+    fn state_with(limit_by: &str, tasks: &[(&str, u16)]) -> State {
+        let mut config = format!("cut-off: 04:00:00.0\nlimit-by: {limit_by}\ntasks:\n");
+        for (slug, spoons) in tasks {
+            config.push_str(&format!(
+                "- {{ slug: {slug}, task: {slug}, weight: 1.0, spoons: {spoons} }}\n"
+            ));
+        }
+        State::from_config_yaml(&config)
+    }
+
+    /// Picks tasks with the given seed, keeping any of today's tasks that are already set.
+    fn pick(state: &mut State, seed: u64) -> PickOutcome {
+        state.set_seed(Some(seed));
+        let limit_by = state.limit_by().clone();
+        pick_tasks(&limit_by, &mut WeightedRandomPicker, state).unwrap()
+    }
+
+    fn sorted(mut slugs: Vec<String>) -> Vec<String> {
+        slugs.sort();
+        slugs
+    }
+
+    #[test]
+    fn picks_tasks_until_the_spoon_budget_is_used() {
+        for seed in 0..20 {
+            let mut state = state_with("{ spoons: 3 }", &[("big", 4), ("one", 1), ("two", 2)]);
+            let outcome = pick(&mut state, seed);
+            assert_eq!(sorted(outcome.new_tasks), ["one", "two"]);
+            assert_eq!(outcome.limited_by, LimitReached::Spoons(3));
+        }
+    }
+
+    #[test]
+    fn picks_variants_that_fit_the_spoon_budget() {
+        let mut state = State::from_config_yaml(
+            r#"
+cut-off: 04:00:00.0
+limit-by:
+  spoons: 2
+tasks:
+- { slug: big, task: Big, weight: 1.0, spoons: 4 }
+- slug: walk
+  task: Walk
+  weight: 1.0
+  spoons: 5
+  variants: [long, { name: short, spoons: 1 }]
+"#,
+        );
+        for seed in 0..20 {
+            state.todays_tasks_mut().clear();
+            assert_eq!(pick(&mut state, seed).new_tasks, ["walk"]);
+            let walk = state.get_task("walk").unwrap();
+            assert_eq!(walk.current_variant().unwrap().name, "short");
+        }
+    }
+
+    #[test]
+    fn keeps_todays_tasks_and_counts_their_spoons() {
+        for seed in 0..20 {
+            let mut state = state_with("{ spoons: 3 }", &[("big", 4), ("one", 1), ("two", 2)]);
+            state.todays_tasks_mut().extend(["two"]);
+            state.get_task("two").unwrap().choose(&state);
+            let outcome = pick(&mut state, seed);
+            assert_eq!(outcome.new_tasks, ["one"]);
+            assert_eq!(Vec::from(state.todays_tasks()), ["one", "two"]);
+        }
+    }
+
+    #[test]
+    fn carried_tasks_only_count_their_spoons_per_day() {
+        for spoons_per_day in [false, true] {
+            let mut state = state_with("{ spoons: 3 }", &[("long", 2), ("one", 1), ("two", 2)]);
+            let long = state.get_task("long").unwrap();
+            long.config.borrow_mut().duration_days = Some(3);
+            long.config.borrow_mut().spoons_per_day = spoons_per_day;
+            long.state.borrow_mut().last_chosen = Some(state.todays_date() - Duration::DAY);
+            state.todays_tasks_mut().extend(["long"]);
+
+            let outcome = pick(&mut state, 1);
+            let expected = if spoons_per_day {
+                vec!["one"]
+            } else {
+                vec!["one", "two"]
+            };
+            assert_eq!(sorted(outcome.new_tasks), expected);
+        }
+    }
+
+    #[test]
+    fn picks_the_minimum_number_of_tasks_over_budget() {
+        for seed in 0..20 {
+            let mut state = state_with(
+                "{ spoons: 2, min-tasks: 2 }",
+                &[("heavy", 5), ("heavier", 5), ("light", 1)],
+            );
+            let outcome = pick(&mut state, seed);
+            assert_eq!(outcome.new_tasks.len(), 2);
+            assert_eq!(outcome.limited_by, LimitReached::Spoons(2));
+        }
+    }
+
+    #[test]
+    fn stops_at_the_task_limit_before_the_spoon_budget() {
+        let mut state = state_with(
+            "{ tasks: 2, spoons: 10 }",
+            &[("a", 1), ("b", 1), ("c", 1), ("d", 1)],
+        );
+        let outcome = pick(&mut state, 3);
+        assert_eq!(outcome.new_tasks.len(), 2);
+        assert_eq!(outcome.limited_by, LimitReached::Tasks(2));
+    }
+
+    #[test]
+    fn stops_when_no_tasks_are_left() {
+        let mut state = state_with("{ tasks: 5 }", &[("a", 1), ("b", 1), ("c", 1)]);
+        let outcome = pick(&mut state, 3);
+        assert_eq!(sorted(outcome.new_tasks), ["a", "b", "c"]);
+        assert_eq!(outcome.limited_by, LimitReached::NoChoosableTasks);
+    }
+
+    const TAGGED_CONFIG: &str = r#"
+cut-off: 04:00:00.0
+limit-by:
+  tasks: 3
+tag-quotas:
+  chores: { max: 1 }
+  outside: { min: 2 }
+tasks:
+- { slug: dishes, task: Dishes, weight: 10.0, spoons: 1, tags: [chores] }
+- { slug: laundry, task: Laundry, weight: 10.0, spoons: 1, tags: [chores] }
+- { slug: mop, task: Mop, weight: 10.0, spoons: 1, tags: [chores] }
+- { slug: read, task: Read, weight: 10.0, spoons: 1 }
+- { slug: walk, task: Walk, weight: 1.0, spoons: 1, tags: [outside] }
+- { slug: garden, task: Garden, weight: 1.0, spoons: 1, tags: [outside, chores] }
+"#;
+
+    #[test]
+    fn tag_quotas_limit_and_prefer_tasks() {
+        for seed in 0..20 {
+            let mut state = State::from_config_yaml(TAGGED_CONFIG);
+            let outcome = pick(&mut state, seed);
+            // Both outside tasks are preferred, and garden uses up the one allowed chore.
+            assert_eq!(sorted(outcome.new_tasks), ["garden", "read", "walk"]);
+            assert_eq!(outcome.unmet_quotas, []);
+        }
+    }
+
+    #[test]
+    fn reports_unmet_tag_quotas() {
+        let mut state = State::from_config_yaml(TAGGED_CONFIG);
+        state.todays_tasks_mut().extend(["dishes"]);
+        state.get_task("dishes").unwrap().choose(&state);
+        let outcome = pick(&mut state, 1);
+        assert_eq!(sorted(outcome.new_tasks), ["read", "walk"]);
+        assert_eq!(
+            outcome.unmet_quotas,
+            [UnmetQuota {
+                tag: String::from("outside"),
+                picked: 1,
+                min: 2,
+            }]
+        );
+    }
+
+    #[test]
+    fn never_picks_tasks_that_exclude_each_other() {
+        let mut state = State::from_config_yaml(
+            r#"
+cut-off: 04:00:00.0
+limit-by:
+  tasks: 4
+exclusion-groups:
+  outdoor: [run, swim]
+tasks:
+- { slug: run, task: Run, weight: 1.0, spoons: 1 }
+- { slug: swim, task: Swim, weight: 1.0, spoons: 1 }
+- { slug: bake, task: Bake, weight: 1.0, spoons: 1, exclusion-groups: [oven] }
+- { slug: roast, task: Roast, weight: 1.0, spoons: 1, exclusion-groups: [oven] }
+- { slug: read, task: Read, weight: 1.0, spoons: 1 }
+"#,
+        );
+        for seed in 0..20 {
+            state.todays_tasks_mut().clear();
+            let outcome = pick(&mut state, seed);
+            let picked = |slug| outcome.new_tasks.iter().any(|s| s == slug);
+            assert_eq!(outcome.new_tasks.len(), 3);
+            assert!(picked("read"));
+            assert!(!(picked("run") && picked("swim")));
+            assert!(!(picked("bake") && picked("roast")));
+            assert_eq!(outcome.limited_by, LimitReached::NoChoosableTasks);
+        }
+    }
+}
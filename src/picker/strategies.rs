@@ -0,0 +1,196 @@
+use super::Picker;
+use crate::{Result, Task, state::State};
+use rand::{RngCore, seq::IndexedRandom};
+use serde::{Deserialize, Serialize};
+use strum::EnumIs;
+
+/// The built-in strategies for picking tasks.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, EnumIs, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum PickStrategy {
+    /// Picks tasks at random, based on their weights.
+    #[default]
+    WeightedRandom,
+    /// Picks the tasks that were chosen the longest time ago.
+    LeastRecentlyChosen,
+    /// Picks tasks in order, starting after the last picked task.
+    RoundRobin,
+    /// Picks every task once, at random, before any task is repeated.
+    ShuffleBag,
+}
+
+impl PickStrategy {
+    pub fn picker(&self) -> Box<dyn Picker> {
+        match self {
+            Self::WeightedRandom => Box::new(WeightedRandomPicker),
+            Self::LeastRecentlyChosen => Box::new(LeastRecentlyChosenPicker),
+            Self::RoundRobin => Box::new(RoundRobinPicker),
+            Self::ShuffleBag => Box::new(ShuffleBagPicker),
+        }
+    }
+}
+
+#[inline]
+fn choose_weighted<'a>(
+    tasks: &[&'a Task],
+    state: &State,
+    rng: &mut dyn RngCore,
+) -> Result<&'a Task> {
+    Ok(*tasks.choose_weighted(rng, |t| t.effective_weight(state))?)
+}
+
+/// Picks tasks at random, based on their effective weights.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct WeightedRandomPicker;
+
+impl Picker for WeightedRandomPicker {
+    fn pick<'a>(
+        &mut self,
+        candidates: &[&'a Task],
+        state: &State,
+        rng: &mut dyn RngCore,
+    ) -> Result<&'a Task> {
+        choose_weighted(candidates, state, rng)
+    }
+}
+
+/// Picks the tasks that were chosen the longest time ago, with tasks that have never been chosen
+/// coming first. Ties are picked at random, based on their weights.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LeastRecentlyChosenPicker;
+
+impl Picker for LeastRecentlyChosenPicker {
+    fn pick<'a>(
+        &mut self,
+        candidates: &[&'a Task],
+        state: &State,
+        rng: &mut dyn RngCore,
+    ) -> Result<&'a Task> {
+        let oldest = candidates.iter().map(|t| t.last_chosen()).min().flatten();
+        let tasks: Vec<_> = candidates
+            .iter()
+            .filter(|t| t.last_chosen() == oldest)
+            .copied()
+            .collect();
+        choose_weighted(&tasks, state, rng)
+    }
+}
+
+/// Picks tasks in order of their slugs, starting after the last picked task and wrapping around
+/// once the end is reached.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RoundRobinPicker;
+
+impl Picker for RoundRobinPicker {
+    fn pick<'a>(
+        &mut self,
+        candidates: &[&'a Task],
+        state: &State,
+        rng: &mut dyn RngCore,
+    ) -> Result<&'a Task> {
+        let next = state
+            .last_picked()
+            .and_then(|last| candidates.iter().find(|t| t.slug() > last));
+        Ok(next.or(candidates.first()).copied().unwrap())
+    }
+}
+
+/// Picks every task once, at random based on their weights, before any task is repeated.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ShuffleBagPicker;
+
+impl Picker for ShuffleBagPicker {
+    fn pick<'a>(
+        &mut self,
+        candidates: &[&'a Task],
+        state: &State,
+        rng: &mut dyn RngCore,
+    ) -> Result<&'a Task> {
+        let tasks: Vec<_> = candidates
+            .iter()
+            .filter(|t| !state.shuffle_bag().contains(t.slug()))
+            .copied()
+            .collect();
+        if tasks.is_empty() {
+            log::debug!("Every task has been picked; refilling the bag.");
+            choose_weighted(candidates, state, rng)
+        } else {
+            choose_weighted(&tasks, state, rng)
+        }
+    }
+
+    fn picked(&mut self, slug: &str, state: &mut State) {
+        let bag = state.shuffle_bag_mut();
+        // If the task was already in the bag, then the bag has been refilled.
+        if !bag.insert(String::from(slug)) {
+            bag.clear();
+            bag.insert(String::from(slug));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::picker::pick_tasks;
+    use pretty_assertions::assert_eq;
+    use time::Duration;
+
+    const CONFIG: &str = r#"
+cut-off: 04:00:00.0
+seed: 1
+limit-by:
+  tasks: 2
+tasks:
+- { slug: a, task: A, weight: 1.0, spoons: 1 }
+- { slug: b, task: B, weight: 2.0, spoons: 1 }
+- { slug: c, task: C, weight: 3.0, spoons: 1 }
+"#;
+
+    /// Picks a new set of today's tasks with the given picker.
+    fn repick(state: &mut State, picker: &mut dyn Picker) -> Vec<String> {
+        state.todays_tasks_mut().clear();
+        state.next_round();
+        let limit_by = state.limit_by().clone();
+        pick_tasks(&limit_by, picker, state).unwrap().new_tasks
+    }
+
+    #[test]
+    fn round_robin_picks_tasks_in_order() {
+        let mut state = State::from_config_yaml(CONFIG);
+        let mut picker = RoundRobinPicker;
+        assert_eq!(repick(&mut state, &mut picker), ["a", "b"]);
+        assert_eq!(repick(&mut state, &mut picker), ["c", "a"]);
+        assert_eq!(repick(&mut state, &mut picker), ["b", "c"]);
+    }
+
+    #[test]
+    fn least_recently_chosen_picks_the_oldest_tasks() {
+        let mut state = State::from_config_yaml(CONFIG);
+        let today = state.todays_date();
+        state.get_task("a").unwrap().state.borrow_mut().last_chosen = Some(today - Duration::DAY);
+        state.get_task("b").unwrap().state.borrow_mut().last_chosen =
+            Some(today - Duration::days(3));
+        assert_eq!(
+            repick(&mut state, &mut LeastRecentlyChosenPicker),
+            ["c", "b"]
+        );
+    }
+
+    #[test]
+    fn shuffle_bag_picks_every_task_before_repeating() {
+        for seed in 0..20 {
+            let mut state = State::from_config_yaml(&CONFIG.replace("tasks: 2", "tasks: 1"));
+            state.set_seed(Some(seed));
+            let mut picker = ShuffleBagPicker;
+            let mut picked: Vec<_> = (0..3)
+                .flat_map(|_| repick(&mut state, &mut picker))
+                .collect();
+            picked.sort();
+            assert_eq!(picked, ["a", "b", "c"]);
+            // The bag is refilled once every task has been picked.
+            assert_eq!(repick(&mut state, &mut picker).len(), 1);
+            assert_eq!(state.shuffle_bag().len(), 1);
+        }
+    }
+}
@@ -1 +1,2 @@
 pub mod once_cell;
+pub mod weekdays;
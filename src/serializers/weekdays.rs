@@ -0,0 +1,20 @@
+use crate::util::parse_weekday;
+use serde::{Deserialize, Deserializer, Serializer, de::Error as _};
+use time::Weekday;
+
+pub fn serialize<S>(value: &[Weekday], serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.collect_seq(value.iter().map(|w| w.to_string().to_lowercase()))
+}
+
+pub fn deserialize<'de, D>(deserializer: D) -> Result<Vec<Weekday>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Vec::<String>::deserialize(deserializer)?
+        .iter()
+        .map(|w| parse_weekday(w).map_err(D::Error::custom))
+        .collect()
+}
@@ -1,12 +1,14 @@
 use crate::{
     Error, RcCell, Result, STATE_DIR, STATE_FILE_PATH,
     config::{Config, LimitTasksBy},
+    history::{self, DayRecord, Event, EventKind},
     task::{Task, TaskConfig, TaskSet, TaskState},
     util::{days_elapsed, dt_with_cutoff, now},
 };
 use serde::{Deserialize, Serialize};
 use std::{
-    collections::HashMap,
+    cell::RefCell,
+    collections::{BTreeSet, HashMap},
     fs::{self, DirBuilder, OpenOptions},
 };
 use time::{Date, Duration, OffsetDateTime, Time};
@@ -18,6 +20,16 @@ pub struct StateModel {
     pub last_generated: OffsetDateTime,
     pub tasks: HashMap<String, RcCell<TaskState>>,
     pub todays_tasks: TaskSet,
+    /// The seed used to pick today's tasks, if any.
+    #[serde(skip_serializing_if = "std::option::Option::is_none")]
+    pub seed: Option<u64>,
+    /// How many times tasks have been picked using the seed.
+    pub rounds: u32,
+    #[serde(skip_serializing_if = "std::option::Option::is_none")]
+    pub last_picked: Option<String>,
+    /// The tasks that have already been picked by the shuffle bag strategy.
+    #[serde(skip_serializing_if = "TaskSet::is_empty")]
+    pub shuffle_bag: TaskSet,
 }
 
 impl Default for StateModel {
@@ -26,6 +38,10 @@ impl Default for StateModel {
             last_generated: now() - Duration::DAY,
             tasks: HashMap::new(),
             todays_tasks: TaskSet::new(),
+            seed: None,
+            rounds: 0,
+            last_picked: None,
+            shuffle_bag: TaskSet::new(),
         }
     }
 }
@@ -58,17 +74,37 @@ pub struct State {
     config: Config,
     model: StateModel,
     tasks: HashMap<String, Task>,
+    /// Events that haven't been written to the history file yet.
+    events: RefCell<Vec<Event>>,
 }
 
 impl State {
     pub fn load() -> Result<Self> {
         let config = Config::load()?;
-        let model = if STATE_FILE_PATH.exists() {
+        let model: StateModel = if STATE_FILE_PATH.exists() {
             let data = fs::read(&*STATE_FILE_PATH)?;
             serde_norway::from_slice(&data)?
         } else {
             StateModel::default()
         };
+        Ok(Self::new(config, model))
+    }
+
+    /// Creates a state with no history from a YAML config, for testing.
+    #[cfg(test)]
+    pub(crate) fn from_config_yaml(config: &str) -> Self {
+        Self::new(
+            Config::from_slice(config.as_bytes()).unwrap(),
+            StateModel::default(),
+        )
+    }
+
+    /// Creates the state from an already loaded config and model.
+    pub fn new(config: Config, mut model: StateModel) -> Self {
+        // Tasks that were added to the config by hand don't have any state yet.
+        for slug in config.task_slugs() {
+            model.tasks.entry(slug).or_default();
+        }
         let mut orphans = Vec::new();
         let mut tasks = HashMap::new();
         for (slug, task_state) in model.tasks.iter() {
@@ -80,22 +116,130 @@ impl State {
             }
         }
         //TODO report on orphans
-        Ok(Self {
+        Self {
             config,
             model,
             tasks,
-        })
+            events: RefCell::new(Vec::new()),
+        }
     }
 
     pub fn save(&self) -> Result<()> {
+        // Checked first so that the state isn't saved without the config.
+        self.config.validate_requirements()?;
         self.model.save()?;
         self.config.save()?;
+        history::append(self.events.take())
+    }
+
+    /// Records an event, which is written to the history file when the state is saved.
+    pub fn record(&self, kind: EventKind) {
+        self.record_on(self.todays_date(), kind);
+    }
+
+    /// Records an event that happened on an earlier day.
+    pub fn record_on(&self, date: Date, kind: EventKind) {
+        self.events.borrow_mut().push(Event::new(date, kind));
+    }
+
+    /// Records that a task is one of today's tasks, along with how it looks today.
+    pub(crate) fn record_generated(&self, task: &Task, carried: bool) {
+        self.record(EventKind::Generated {
+            task: String::from(task.slug()),
+            carried,
+            picked_as: task.is_template().then(|| task.rendered_task()),
+            variant: task.current_variant().map(|v| v.name),
+        });
+    }
+
+    /// Records that a task was completed, if it wasn't already.
+    fn record_completed(&self, task: &Task, was_completed: bool) {
+        if was_completed || !task.completed() {
+            return;
+        }
+        self.record(EventKind::Completed {
+            task: String::from(task.slug()),
+            variant: task.current_variant().map(|v| v.name),
+            amount: task.target().map(|_| task.state.borrow().progress),
+            completed_as: task.is_template().then(|| task.rendered_task()),
+        });
+    }
+
+    /// Completes a task. If an amount is given, it's logged towards the task's target instead,
+    /// which is only allowed for today's tasks.
+    pub fn complete_task<S: AsRef<str>>(&self, slug: S, amount: Option<f64>) -> Result<()> {
+        let task = self
+            .get_task(slug.as_ref())
+            .ok_or_else(|| Error::task_not_found(slug.as_ref()))?;
+        if let Some(amount) = amount {
+            if !(amount.is_finite() && amount > 0.0) {
+                return Err(Error::simple(format!(
+                    "Amounts must be more than 0, not {amount}."
+                )));
+            }
+            if !self.todays_tasks().contains(slug.as_ref()) {
+                return Err(Error::task_not_picked_on(slug, self.todays_date()));
+            }
+        }
+        let was_completed = task.completed();
+        match amount {
+            Some(amount) => task.log_amount(amount),
+            None => task.complete(),
+        }
+        self.record_completed(task, was_completed);
+        Ok(())
+    }
+
+    /// Completes a task on an earlier day, using the history to check that the task was one of
+    /// that day's tasks. Tasks that were already completed that day are left alone.
+    pub fn complete_task_on<S: AsRef<str>>(
+        &self,
+        slug: S,
+        date: Date,
+        record: &DayRecord,
+    ) -> Result<()> {
+        let slug = slug.as_ref();
+        let task = self
+            .get_task(slug)
+            .ok_or_else(|| Error::task_not_found(slug))?;
+        if !record.tasks.contains(slug) {
+            return Err(Error::task_not_picked_on(slug, date));
+        }
+        if record.completed.contains(slug) {
+            return Ok(());
+        }
+        task.complete_earlier();
+        let picked_as = record.picked_as.get(slug).cloned().unwrap_or_default();
+        self.record_on(
+            date,
+            EventKind::Completed {
+                task: String::from(slug),
+                variant: picked_as.variant,
+                amount: task.target().map(|t| t.amount),
+                completed_as: picked_as.title,
+            },
+        );
+        Ok(())
+    }
+
+    /// Checks or unchecks an item in one of today's tasks' checklist.
+    pub fn check_task<S: AsRef<str>>(&self, slug: S, item: &str, checked: bool) -> Result<()> {
+        let task = self
+            .get_task(slug.as_ref())
+            .filter(|_| self.todays_tasks().contains(slug.as_ref()))
+            .ok_or_else(|| Error::task_not_found(slug.as_ref()))?;
+        let was_completed = task.completed();
+        task.check(item, checked)?;
+        self.record_completed(task, was_completed);
         Ok(())
     }
 
     pub fn enable_task<S: AsRef<str>>(&self, slug: S) -> Result<()> {
         if let Some(task) = self.tasks.get(slug.as_ref()) {
             task.enable();
+            self.record(EventKind::Enabled {
+                task: String::from(slug.as_ref()),
+            });
             Ok(())
         } else {
             Err(Error::task_not_found(slug))
@@ -114,6 +258,9 @@ impl State {
     pub fn disable_task<S: AsRef<str>>(&self, slug: S) -> Result<()> {
         if let Some(task) = self.tasks.get(slug.as_ref()) {
             task.disable();
+            self.record(EventKind::Disabled {
+                task: String::from(slug.as_ref()),
+            });
             Ok(())
         } else {
             Err(Error::task_not_found(slug))
@@ -129,13 +276,77 @@ impl State {
         Ok(())
     }
 
+    pub fn pin_task<S: AsRef<str>>(&self, slug: S) -> Result<()> {
+        if let Some(task) = self.tasks.get(slug.as_ref()) {
+            task.pin();
+            Ok(())
+        } else {
+            Err(Error::task_not_found(slug))
+        }
+    }
+
+    pub fn pin_tasks<I, S>(&self, slugs: I) -> Result<()>
+    where
+        S: AsRef<str>,
+        I: IntoIterator<Item = S>,
+    {
+        slugs.into_iter().try_for_each(|t| self.pin_task(t))?;
+        Ok(())
+    }
+
+    pub fn unpin_task<S: AsRef<str>>(&self, slug: S) -> Result<()> {
+        if let Some(task) = self.tasks.get(slug.as_ref()) {
+            task.unpin();
+            Ok(())
+        } else {
+            Err(Error::task_not_found(slug))
+        }
+    }
+
+    pub fn unpin_tasks<I, S>(&self, slugs: I) -> Result<()>
+    where
+        S: AsRef<str>,
+        I: IntoIterator<Item = S>,
+    {
+        slugs.into_iter().try_for_each(|t| self.unpin_task(t))?;
+        Ok(())
+    }
+
+    #[inline]
+    pub fn pinned_tasks(&self) -> Vec<&Task> {
+        self.tasks.values().filter(|t| t.pinned()).collect()
+    }
+
+    #[inline]
+    pub fn unpinned_tasks(&self) -> Vec<&Task> {
+        self.tasks.values().filter(|t| !t.pinned()).collect()
+    }
+
     pub fn get_task<S: AsRef<str>>(&self, slug: S) -> Option<&Task> {
         self.tasks.get(slug.as_ref())
     }
 
+    /// Makes sure that none of the tasks are required by a task that isn't also being removed.
+    fn check_removable(&self, slugs: &BTreeSet<String>) -> Result<()> {
+        for slug in slugs {
+            if let Some(required_by) = self.config.required_by(slug, slugs) {
+                return Err(Error::task_is_required(slug, required_by));
+            }
+        }
+        Ok(())
+    }
+
     pub fn remove_task<S: AsRef<str>>(&mut self, slug: S) -> Result<()> {
+        self.check_removable(&BTreeSet::from([String::from(slug.as_ref())]))?;
+        self.remove_task_unchecked(slug)
+    }
+
+    fn remove_task_unchecked<S: AsRef<str>>(&mut self, slug: S) -> Result<()> {
         if self.tasks.remove(slug.as_ref()).is_some() {
             self.model.tasks.remove(slug.as_ref());
+            self.record(EventKind::Removed {
+                task: String::from(slug.as_ref()),
+            });
             self.config.remove_task(slug);
             Ok(())
         } else {
@@ -148,7 +359,14 @@ impl State {
         I: IntoIterator<Item = S>,
         S: AsRef<str>,
     {
-        tasks.into_iter().try_for_each(|s| self.remove_task(s))?;
+        let slugs: BTreeSet<_> = tasks
+            .into_iter()
+            .map(|s| String::from(s.as_ref()))
+            .collect();
+        self.check_removable(&slugs)?;
+        slugs
+            .into_iter()
+            .try_for_each(|s| self.remove_task_unchecked(s))?;
         Ok(())
     }
 
@@ -159,6 +377,7 @@ impl State {
         self.model
             .tasks
             .insert(slug.clone(), RcCell::clone(&task.state));
+        self.record(EventKind::Added { task: slug.clone() });
         self.tasks.insert(slug, task);
         Ok(())
     }
@@ -173,6 +392,9 @@ impl State {
 
     pub fn update_task(&self, task_config: TaskConfig) -> Result<()> {
         if let Some(task) = self.tasks.get(task_config.slug()) {
+            self.record(EventKind::Updated {
+                task: String::from(task_config.slug()),
+            });
             let mut borrowed = task.config.borrow_mut();
             (*borrowed) += task_config;
             Ok(())
@@ -211,7 +433,10 @@ impl State {
 
     #[inline]
     pub fn tasks(&self) -> Vec<&Task> {
-        self.tasks.values().collect()
+        let mut tasks: Vec<_> = self.tasks.values().collect();
+        // Sorted so that picking with a seed is reproducible.
+        tasks.sort_by(|t1, t2| t1.slug().cmp(t2.slug()));
+        tasks
     }
 
     #[inline]
@@ -272,6 +497,49 @@ impl State {
         self.model.last_generated = now();
     }
 
+    /// Returns the slug of the most recently picked task.
+    #[inline]
+    pub fn last_picked(&self) -> Option<&str> {
+        self.model.last_picked.as_deref()
+    }
+
+    #[inline]
+    pub(crate) fn set_last_picked<S: AsRef<str>>(&mut self, slug: S) {
+        self.model.last_picked = Some(String::from(slug.as_ref()));
+    }
+
+    #[inline]
+    pub fn shuffle_bag(&self) -> &TaskSet {
+        &self.model.shuffle_bag
+    }
+
+    #[inline]
+    pub fn shuffle_bag_mut(&mut self) -> &mut TaskSet {
+        &mut self.model.shuffle_bag
+    }
+
+    #[inline]
+    pub fn seed(&self) -> Option<u64> {
+        self.model.seed
+    }
+
+    #[inline]
+    pub fn rounds(&self) -> u32 {
+        self.model.rounds
+    }
+
+    /// Sets the seed to pick tasks with and restarts its rounds.
+    #[inline]
+    pub fn set_seed(&mut self, seed: Option<u64>) {
+        self.model.seed = seed;
+        self.model.rounds = 0;
+    }
+
+    #[inline]
+    pub(crate) fn next_round(&mut self) {
+        self.model.rounds += 1;
+    }
+
     #[inline]
     pub fn days_since_today(&self, date: Date) -> i64 {
         days_elapsed(self.config.today(), date)
@@ -280,7 +548,12 @@ impl State {
     /// Returns the sum of all of today's task's spoons.
     #[inline]
     pub fn current_spoons(&self) -> u16 {
-        self.tasks.values().map(|t| t.spoons()).sum()
+        self.model
+            .todays_tasks
+            .iter()
+            .filter_map(|s| self.tasks.get(s))
+            .map(|t| t.todays_spoons(self))
+            .sum()
     }
 }
 
@@ -296,9 +569,10 @@ impl State {
         *self.config.cut_off()
     }
 
+    /// Returns today's limit, taking the limit schedule into account.
     #[inline]
     pub fn limit_by(&self) -> &LimitTasksBy {
-        self.config.limit_by()
+        self.config.todays_limit_by()
     }
 
     #[inline]
@@ -306,3 +580,67 @@ impl State {
         self.config.today()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    const CONFIG: &str = r#"
+cut-off: 04:00:00.0
+limit-by:
+  tasks: 1
+tasks:
+- { slug: run, task: Run, weight: 1.0, spoons: 1, target: { amount: 5, unit: km } }
+- { slug: swim, task: Swim, weight: 1.0, spoons: 1, target: { amount: 10 } }
+"#;
+
+    #[test]
+    fn logs_amounts_only_for_todays_tasks() {
+        let mut state = State::from_config_yaml(CONFIG);
+        state.todays_tasks_mut().extend(["run"]);
+        let progress = |state: &State, slug| state.get_task(slug).unwrap().state.borrow().progress;
+
+        for amount in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+            assert!(state.complete_task("run", Some(amount)).is_err());
+        }
+        assert!(state.complete_task("swim", Some(5.0)).is_err());
+        assert_eq!(progress(&state, "swim"), 0.0);
+
+        state.complete_task("run", Some(3.0)).unwrap();
+        assert_eq!(progress(&state, "run"), 3.0);
+        assert!(!state.get_task("run").unwrap().completed());
+        state.complete_task("run", Some(2.0)).unwrap();
+        assert!(state.get_task("run").unwrap().completed());
+    }
+
+    #[test]
+    fn backdated_completions_use_how_the_task_was_picked() {
+        let state = State::from_config_yaml(CONFIG);
+        let date = state.todays_date() - Duration::DAY;
+        let record = history::days(&[Event::new(
+            date,
+            EventKind::Generated {
+                task: String::from("run"),
+                carried: false,
+                picked_as: Some(String::from("Run 4 km")),
+                variant: Some(String::from("Hills")),
+            },
+        )])
+        .remove(&date)
+        .unwrap();
+
+        state.complete_task_on("run", date, &record).unwrap();
+        let events = state.events.borrow();
+        assert_eq!(events[0].date, date);
+        assert_eq!(
+            events[0].kind,
+            EventKind::Completed {
+                task: String::from("run"),
+                variant: Some(String::from("Hills")),
+                amount: Some(5.0),
+                completed_as: Some(String::from("Run 4 km")),
+            }
+        );
+    }
+}
@@ -0,0 +1,242 @@
+use crate::{
+    State,
+    history::{Event, EventKind},
+};
+use serde::{Serialize, Serializer};
+use std::{
+    cmp::Ordering,
+    collections::{BTreeMap, HashSet},
+};
+use time::{Date, Weekday};
+
+/// How often something was chosen, completed and refreshed away.
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+#[serde(into = "CountsWithRate")]
+pub struct Counts {
+    pub chosen: u32,
+    pub completed: u32,
+    pub refreshed: u32,
+}
+
+impl Counts {
+    /// How many of the times it was chosen it was also completed. Returns `None` if it was never
+    /// chosen.
+    pub fn completion_rate(&self) -> Option<f64> {
+        if self.chosen > 0 {
+            Some(self.completed as f64 / self.chosen as f64)
+        } else {
+            None
+        }
+    }
+
+    fn add(&mut self, kind: &EventKind) {
+        match kind {
+            EventKind::Generated { carried: false, .. } => self.chosen += 1,
+            EventKind::Completed { .. } => self.completed += 1,
+            EventKind::Refreshed { .. } | EventKind::Reset { .. } => self.refreshed += 1,
+            _ => {}
+        }
+    }
+}
+
+/// [`Counts`] along with the completion rate, for serializing.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "kebab-case")]
+struct CountsWithRate {
+    chosen: u32,
+    completed: u32,
+    refreshed: u32,
+    #[serde(skip_serializing_if = "std::option::Option::is_none")]
+    completion_rate: Option<f64>,
+}
+
+impl From<Counts> for CountsWithRate {
+    fn from(counts: Counts) -> Self {
+        Self {
+            chosen: counts.chosen,
+            completed: counts.completed,
+            refreshed: counts.refreshed,
+            completion_rate: counts.completion_rate(),
+        }
+    }
+}
+
+/// A weekday that's ordered starting from Monday and serialized as its lowercase name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WeekdayKey(pub Weekday);
+
+impl Ord for WeekdayKey {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0
+            .number_days_from_monday()
+            .cmp(&other.0.number_days_from_monday())
+    }
+}
+
+impl PartialOrd for WeekdayKey {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Serialize for WeekdayKey {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0.to_string().to_lowercase())
+    }
+}
+
+/// Statistics about the task history, optionally limited to a range of dates.
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Stats {
+    #[serde(skip_serializing_if = "std::option::Option::is_none")]
+    pub from: Option<Date>,
+    #[serde(skip_serializing_if = "std::option::Option::is_none")]
+    pub until: Option<Date>,
+    pub total: Counts,
+    pub tasks: BTreeMap<String, Counts>,
+    pub tags: BTreeMap<String, Counts>,
+    pub weekdays: BTreeMap<WeekdayKey, Counts>,
+}
+
+impl Stats {
+    /// Tallies up the events that fall within the date range. Completions only count if the task
+    /// was picked that day. Tags are looked up from the tasks' current config, so tasks that have
+    /// been removed don't count towards any tags.
+    pub fn from_events<'a, I>(
+        events: I,
+        state: &State,
+        from: Option<Date>,
+        until: Option<Date>,
+    ) -> Self
+    where
+        I: IntoIterator<Item = &'a Event>,
+    {
+        let mut stats = Self {
+            from,
+            until,
+            ..Default::default()
+        };
+        let events: Vec<_> = events
+            .into_iter()
+            .filter(|e| from.is_none_or(|f| e.date >= f) && until.is_none_or(|u| e.date <= u))
+            .collect();
+        let picked: HashSet<_> = events
+            .iter()
+            .filter(|e| e.kind.is_generated())
+            .map(|e| (e.date, e.kind.task()))
+            .collect();
+        for event in events.iter() {
+            let kind = &event.kind;
+            let counted = match kind {
+                EventKind::Generated { .. }
+                | EventKind::Refreshed { .. }
+                | EventKind::Reset { .. } => true,
+                EventKind::Completed { task, .. } => picked.contains(&(event.date, task.as_str())),
+                _ => false,
+            };
+            if !counted {
+                continue;
+            }
+            stats.total.add(kind);
+            stats
+                .tasks
+                .entry(String::from(kind.task()))
+                .or_default()
+                .add(kind);
+            if let Some(task) = state.get_task(kind.task()) {
+                for tag in task.tags() {
+                    stats.tags.entry(tag).or_default().add(kind);
+                }
+            }
+            stats
+                .weekdays
+                .entry(WeekdayKey(event.date.weekday()))
+                .or_default()
+                .add(kind);
+        }
+        stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use time::macros::date;
+
+    const CONFIG: &str = r#"
+cut-off: 04:00:00.0
+limit-by:
+  tasks: 2
+tasks:
+- { slug: a, task: A, weight: 1.0, spoons: 1, tags: [chores] }
+- { slug: b, task: B, weight: 1.0, spoons: 1 }
+- { slug: c, task: C, weight: 1.0, spoons: 1 }
+"#;
+
+    fn event(date: Date, kind: EventKind) -> Event {
+        Event::new(date, kind)
+    }
+
+    fn generated(task: &str, carried: bool) -> EventKind {
+        EventKind::Generated {
+            task: String::from(task),
+            carried,
+            picked_as: None,
+            variant: None,
+        }
+    }
+
+    fn completed(task: &str) -> EventKind {
+        EventKind::Completed {
+            task: String::from(task),
+            variant: None,
+            amount: None,
+            completed_as: None,
+        }
+    }
+
+    #[test]
+    fn carried_tasks_count_once() {
+        let state = State::from_config_yaml(CONFIG);
+        let events = [
+            event(date!(2026 - 10 - 14), generated("a", false)),
+            event(date!(2026 - 10 - 15), generated("a", true)),
+            event(date!(2026 - 10 - 15), completed("a")),
+        ];
+        let counts = Stats::from_events(&events, &state, None, None).tasks["a"];
+        assert_eq!((counts.chosen, counts.completed), (1, 1));
+    }
+
+    #[test]
+    fn only_counts_completions_of_picked_tasks() {
+        let state = State::from_config_yaml(CONFIG);
+        let friday = date!(2026 - 10 - 16);
+        let events = [
+            event(friday, generated("a", false)),
+            event(friday, generated("b", false)),
+            event(friday, completed("a")),
+            // Not picked that day, so it doesn't count.
+            event(friday, completed("c")),
+            event(
+                friday,
+                EventKind::Reset {
+                    task: String::from("b"),
+                },
+            ),
+        ];
+        let stats = Stats::from_events(&events, &state, None, None);
+
+        let total = stats.total;
+        assert_eq!((total.chosen, total.completed, total.refreshed), (2, 1, 1));
+        assert_eq!(total.completion_rate(), Some(0.5));
+        assert_eq!(stats.tasks["b"].refreshed, 1);
+        assert!(!stats.tasks.contains_key("c"));
+        assert_eq!(stats.tags["chores"].completion_rate(), Some(1.0));
+        assert_eq!(
+            stats.weekdays[&WeekdayKey(Weekday::Friday)].completion_rate(),
+            Some(0.5)
+        );
+    }
+}
@@ -0,0 +1,281 @@
+use crate::{
+    State,
+    history::{self, DayRecord, Event},
+};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use time::{Date, Duration};
+
+/// How many days of history are read to work out the streaks, so that the whole history isn't
+/// read every time today's tasks are shown. Streaks longer than this stop growing.
+pub const STREAK_HISTORY_DAYS: i64 = 366;
+
+/// The current streaks, worked out from the history along with today's tasks.
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Streaks {
+    /// How many days in a row all of the day's tasks were completed. Today only counts once all of
+    /// its tasks are complete, but doesn't break the streak until it's over.
+    pub days: u32,
+    /// How many freeze days are left this month, if streak freezes are configured.
+    #[serde(skip_serializing_if = "std::option::Option::is_none")]
+    pub freezes_left: Option<u32>,
+    /// How many times in a row each task was completed after being picked. Tasks without a streak
+    /// are left out.
+    #[serde(skip_serializing_if = "std::collections::BTreeMap::is_empty")]
+    pub tasks: BTreeMap<String, u32>,
+}
+
+/// Returns `true` if the task takes more than one day to complete.
+fn is_multi_day(state: &State, slug: &str) -> bool {
+    state
+        .get_task(slug)
+        .and_then(|t| t.duration_days())
+        .is_some_and(|d| d > 1)
+}
+
+/// Returns `true` if every task picked that day was completed. Multi-day tasks only count towards
+/// the day they're completed on.
+fn is_complete(state: &State, record: &DayRecord) -> bool {
+    !(record.tasks.is_empty() && record.completed.is_empty())
+        && record
+            .tasks
+            .iter()
+            .filter(|t| !is_multi_day(state, t))
+            .all(|t| record.completed.contains(t))
+}
+
+impl Streaks {
+    /// Works out the streaks from the last [`STREAK_HISTORY_DAYS`] of the history file. If the
+    /// history can't be read, a warning is logged and only today's tasks are taken into account.
+    pub fn load(state: &State) -> Self {
+        let since = state.todays_date() - Duration::days(STREAK_HISTORY_DAYS);
+        let events = history::read_since(Some(since)).unwrap_or_else(|error| {
+            log::warn!("Unable to read the history: {error}");
+            Vec::new()
+        });
+        Self::from_events(&events, state)
+    }
+
+    pub fn from_events<'a, I>(events: I, state: &State) -> Self
+    where
+        I: IntoIterator<Item = &'a Event>,
+    {
+        let today = state.todays_date();
+        let days = history::days_until_today(events, state);
+
+        let freezes = state.config().streak_freezes().unwrap_or(0);
+        let mut streaks = Self::default();
+        let mut frozen = BTreeSet::new();
+        let mut pending = Vec::new();
+        let mut used = BTreeMap::new();
+        let first = days.keys().next().copied().unwrap_or(today);
+        let mut date = today;
+        while date >= first {
+            if days.get(&date).is_some_and(|r| is_complete(state, r)) {
+                streaks.days += 1;
+                // Freezes only count once there's a completed day before them.
+                frozen.extend(pending.drain(..));
+            } else if date != today {
+                let used = used.entry((date.year(), date.month())).or_insert(0);
+                if *used < freezes {
+                    *used += 1;
+                    pending.push(date);
+                } else {
+                    break;
+                }
+            }
+            let Some(previous) = date.previous_day() else {
+                break;
+            };
+            date = previous;
+        }
+        if let Some(freezes) = state.config().streak_freezes() {
+            let used = frozen
+                .iter()
+                .filter(|d| d.year() == today.year() && d.month() == today.month())
+                .count() as u32;
+            streaks.freezes_left = Some(freezes.saturating_sub(used));
+        }
+
+        for slug in state.task_slugs() {
+            let streak = Self::task_streak(state, &slug, &days, &frozen);
+            if streak > 0 {
+                streaks.tasks.insert(slug, streak);
+            }
+        }
+        streaks
+    }
+
+    /// Counts back through the days a task was picked on. Each pick counts if the task was
+    /// completed before its duration was up, and breaks the streak otherwise unless it's still in
+    /// progress or the day was frozen.
+    fn task_streak(
+        state: &State,
+        slug: &str,
+        days: &BTreeMap<Date, DayRecord>,
+        frozen: &BTreeSet<Date>,
+    ) -> u32 {
+        let today = state.todays_date();
+        let duration = state
+            .get_task(slug)
+            .and_then(|t| t.duration_days())
+            .unwrap_or(1)
+            .max(1);
+        let mut streak = 0;
+        let picks = days
+            .iter()
+            .rev()
+            .filter(|(_, r)| r.tasks.contains(slug) && !r.carried.contains(slug));
+        for (date, _) in picks {
+            let end = *date + Duration::days(duration as i64 - 1);
+            if days
+                .range(*date..=end)
+                .any(|(_, r)| r.completed.contains(slug))
+            {
+                streak += 1;
+            } else if end < today && !frozen.contains(date) {
+                break;
+            }
+        }
+        streak
+    }
+
+    /// The task's current streak.
+    #[inline]
+    pub fn task(&self, slug: &str) -> u32 {
+        self.tasks.get(slug).copied().unwrap_or(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Config, history::EventKind, state::StateModel};
+    use pretty_assertions::assert_eq;
+    use time::macros::date;
+
+    const TODAY: Date = date!(2026 - 10 - 16);
+
+    fn state(freezes: Option<u32>) -> State {
+        let mut config = String::from(
+            r#"
+cut-off: 04:00:00.0
+limit-by:
+  tasks: 2
+tasks:
+- { slug: a, task: A, weight: 1.0, spoons: 1 }
+- { slug: b, task: B, weight: 1.0, spoons: 1 }
+- { slug: long, task: Long, weight: 1.0, spoons: 1, duration-days: 3 }
+"#,
+        );
+        if let Some(freezes) = freezes {
+            config.push_str(&format!("streak-freezes: {freezes}\n"));
+        }
+        let config = Config::from_slice(config.as_bytes())
+            .unwrap()
+            .with_today(TODAY);
+        State::new(config, StateModel::default())
+    }
+
+    /// Creates the events for a day, where each task is given as `slug` if it was picked and
+    /// completed, `slug?` if it was only picked, `slug~` if it was only carried over, or `slug!`
+    /// if it was only completed.
+    fn day(date: Date, tasks: &[&str]) -> Vec<Event> {
+        let mut events = Vec::new();
+        for task in tasks {
+            let slug = String::from(task.trim_end_matches(['?', '!', '~']));
+            if !task.ends_with('!') {
+                events.push(Event::new(
+                    date,
+                    EventKind::Generated {
+                        task: slug.clone(),
+                        carried: task.ends_with('~'),
+                        picked_as: None,
+                        variant: None,
+                    },
+                ));
+            }
+            if !task.ends_with(['?', '~']) {
+                events.push(Event::new(
+                    date,
+                    EventKind::Completed {
+                        task: slug,
+                        variant: None,
+                        amount: None,
+                        completed_as: None,
+                    },
+                ));
+            }
+        }
+        events
+    }
+
+    fn days_ago(days: i64) -> Date {
+        TODAY - Duration::days(days)
+    }
+
+    #[test]
+    fn counts_completed_days() {
+        let events = [
+            day(days_ago(4), &["a?"]),
+            day(days_ago(3), &["a", "b"]),
+            day(days_ago(2), &["a"]),
+            day(days_ago(1), &["b"]),
+            day(TODAY, &["a?", "b"]),
+        ]
+        .concat();
+        let streaks = Streaks::from_events(&events, &state(None));
+        assert_eq!(streaks.days, 3);
+        assert_eq!(streaks.freezes_left, None);
+        assert_eq!(streaks.task("a"), 2);
+        assert_eq!(streaks.task("b"), 3);
+    }
+
+    #[test]
+    fn freezes_cover_missed_days() {
+        let events = [
+            day(days_ago(5), &["a"]),
+            day(days_ago(4), &["a?"]),
+            day(days_ago(3), &["a"]),
+            day(days_ago(1), &["a"]),
+            day(TODAY, &["a"]),
+        ]
+        .concat();
+        let streaks = Streaks::from_events(&events, &state(None));
+        assert_eq!(streaks.days, 2);
+        assert_eq!(streaks.task("a"), 3);
+
+        let streaks = Streaks::from_events(&events, &state(Some(1)));
+        assert_eq!(streaks.days, 3);
+        assert_eq!(streaks.freezes_left, Some(0));
+
+        let streaks = Streaks::from_events(&events, &state(Some(2)));
+        assert_eq!(streaks.days, 4);
+        assert_eq!(streaks.freezes_left, Some(0));
+        assert_eq!(streaks.task("a"), 4);
+    }
+
+    #[test]
+    fn unused_freezes_are_not_counted() {
+        let events = [day(days_ago(3), &["a?"]), day(TODAY, &["a?"])].concat();
+        let streaks = Streaks::from_events(&events, &state(Some(3)));
+        assert_eq!(streaks.days, 0);
+        assert_eq!(streaks.freezes_left, Some(3));
+    }
+
+    #[test]
+    fn multi_day_tasks_count_when_completed() {
+        let events = [
+            day(days_ago(4), &["long?", "a"]),
+            day(days_ago(3), &["long~", "b"]),
+            day(days_ago(2), &["long~", "long!", "a"]),
+            day(days_ago(1), &["long?", "b"]),
+        ]
+        .concat();
+        let streaks = Streaks::from_events(&events, &state(None));
+        assert_eq!(streaks.days, 4);
+        // The latest pick is still within its duration.
+        assert_eq!(streaks.task("long"), 1);
+    }
+}
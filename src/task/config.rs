@@ -1,15 +1,56 @@
-use super::{DEFAULT_SPOONS, DEFAULT_WEIGHT};
-use crate::config::DisabledOptions;
+use super::{DEFAULT_SPOONS, DEFAULT_WEIGHT, Recurrence, TaskVariant, VariantSource};
+use crate::{Error, config::DisabledOptions, util::MonthDay};
 use derive_builder::Builder;
 use getset::Getters;
 use serde::{Deserialize, Serialize};
-use std::{cell::OnceCell, marker::PhantomData, ops::AddAssign};
+use std::{
+    cell::OnceCell, collections::BTreeMap, fmt, marker::PhantomData, ops::AddAssign, str::FromStr,
+};
+use time::{Date, Weekday};
 
 #[inline]
 fn get_default_slug<S: AsRef<str>>(task: S) -> String {
     slug::slugify(task.as_ref())
 }
 
+/// A numeric goal for a task, such as `20 reps`. Tasks with a target are only complete once the
+/// amounts logged for them in a day add up to it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Target {
+    pub amount: f64,
+    #[serde(default, skip_serializing_if = "std::option::Option::is_none")]
+    pub unit: Option<String>,
+}
+
+impl FromStr for Target {
+    type Err = Error;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let value = value.trim();
+        let (amount, unit) = value.split_once(' ').unwrap_or((value, ""));
+        let amount = amount
+            .parse()
+            .ok()
+            .filter(|a: &f64| *a > 0.0)
+            .ok_or_else(|| Error::simple(format!("'{value}' is not a valid target")))?;
+        let unit = unit.trim();
+        Ok(Self {
+            amount,
+            unit: (!unit.is_empty()).then(|| String::from(unit)),
+        })
+    }
+}
+
+impl fmt::Display for Target {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.unit.as_ref() {
+            Some(unit) => write!(f, "{} {unit}", self.amount),
+            None => write!(f, "{}", self.amount),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, Builder, Getters)]
 #[serde(rename_all = "kebab-case")]
 #[builder(name = "TaskBuilder")]
@@ -38,6 +79,69 @@ pub struct TaskConfig {
     pub disabled: DisabledOptions,
     #[serde(default, skip_serializing_if = "std::vec::Vec::is_empty")]
     pub tags: Vec<String>,
+    /// Groups of tasks that can't be picked on the same day as each other.
+    #[builder(default)]
+    #[serde(default, skip_serializing_if = "std::vec::Vec::is_empty")]
+    pub exclusion_groups: Vec<String>,
+    /// Other tasks that need to be completed a number of times before this task can be chosen.
+    #[builder(default)]
+    #[serde(default, skip_serializing_if = "std::collections::BTreeMap::is_empty")]
+    pub requires: BTreeMap<String, u32>,
+    /// The days of the week the task can be chosen on. If empty, it can be chosen on any day.
+    #[builder(default)]
+    #[serde(
+        default,
+        with = "crate::serializers::weekdays",
+        skip_serializing_if = "std::vec::Vec::is_empty"
+    )]
+    pub weekdays: Vec<Weekday>,
+    /// The first day of the year the task can be chosen on.
+    #[builder(default)]
+    #[serde(default, skip_serializing_if = "std::option::Option::is_none")]
+    pub available_from: Option<MonthDay>,
+    /// The last day of the year the task can be chosen on.
+    #[builder(default)]
+    #[serde(default, skip_serializing_if = "std::option::Option::is_none")]
+    pub available_until: Option<MonthDay>,
+    /// A recurrence rule for the task. On days the rule fires, the task is always picked.
+    #[builder(default)]
+    #[serde(default, skip_serializing_if = "std::option::Option::is_none")]
+    pub recurrence: Option<Recurrence>,
+    /// Pinned tasks are always part of today's tasks.
+    #[builder(default)]
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub pinned: bool,
+    /// Concrete options for the task, one of which is chosen whenever the task is picked.
+    #[builder(default)]
+    #[serde(default, skip_serializing_if = "std::vec::Vec::is_empty")]
+    pub variants: Vec<TaskVariant>,
+    /// A file with more variants for the task.
+    #[builder(default)]
+    #[serde(default, skip_serializing_if = "std::option::Option::is_none")]
+    pub variants_from: Option<VariantSource>,
+    /// Ordered steps for the task, such as the lessons of a course. Each time the task is
+    /// completed, it moves on to the next step, and it's retired after the last one.
+    #[builder(default)]
+    #[serde(default, skip_serializing_if = "std::vec::Vec::is_empty")]
+    pub steps: Vec<String>,
+    /// A numeric goal for the task, such as a number of reps or minutes.
+    #[builder(default)]
+    #[serde(default, skip_serializing_if = "std::option::Option::is_none")]
+    pub target: Option<Target>,
+    /// Items to tick off individually. The task is completed once all of them are checked.
+    #[builder(default)]
+    #[serde(default, skip_serializing_if = "std::vec::Vec::is_empty")]
+    pub checklist: Vec<String>,
+    /// How many days the task stays part of today's tasks once it's picked, until it's
+    /// completed.
+    #[builder(default)]
+    #[serde(default, skip_serializing_if = "std::option::Option::is_none")]
+    pub duration_days: Option<u32>,
+    /// If `true`, a multi-day task's spoons count towards every day it's part of, instead of
+    /// only the day it was picked.
+    #[builder(default)]
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub spoons_per_day: bool,
 }
 
 impl TaskBuilder {
@@ -78,6 +182,13 @@ impl TaskConfig {
         self.slug.get_or_init(|| get_default_slug(&self.task))
     }
 
+    /// Returns `true` if the task can be chosen on the given date, based on its weekdays and
+    /// availability window.
+    pub fn available_on(&self, date: Date) -> bool {
+        (self.weekdays.is_empty() || self.weekdays.contains(&date.weekday()))
+            && MonthDay::in_window(date, self.available_from, self.available_until)
+    }
+
     #[inline]
     pub fn enable(&mut self) {
         self.disabled = DisabledOptions::Enabled;
@@ -88,6 +199,16 @@ impl TaskConfig {
         self.disabled = DisabledOptions::Disabled;
     }
 
+    #[inline]
+    pub fn pin(&mut self) {
+        self.pinned = true;
+    }
+
+    #[inline]
+    pub fn unpin(&mut self) {
+        self.pinned = false;
+    }
+
     /// Takes the values from the `other` argument, and overrides the values in this struct as long
     /// as the value in the other struct is not the default value. **Note**: the `slug` property is
     /// never overwritten.
@@ -103,6 +224,9 @@ impl TaskConfig {
         if other.weight != DEFAULT_WEIGHT {
             self.weight = other.weight;
         }
+        if other.spoons != DEFAULT_SPOONS {
+            self.spoons = other.spoons;
+        }
         if other.min_frequency.unwrap_or(0) != 0 {
             self.min_frequency = other.min_frequency;
         }
@@ -117,6 +241,52 @@ impl TaskConfig {
                 self.tags.push(tag);
             }
         }
+        for group in other.exclusion_groups.into_iter() {
+            if !self.exclusion_groups.contains(&group) {
+                self.exclusion_groups.push(group);
+            }
+        }
+        self.requires.extend(other.requires);
+        for weekday in other.weekdays.into_iter() {
+            if !self.weekdays.contains(&weekday) {
+                self.weekdays.push(weekday);
+            }
+        }
+        if other.available_from.is_some() {
+            self.available_from = other.available_from;
+        }
+        if other.available_until.is_some() {
+            self.available_until = other.available_until;
+        }
+        if other.recurrence.is_some() {
+            self.recurrence = other.recurrence;
+        }
+        if other.pinned {
+            self.pinned = true;
+        }
+        merge_variants(&mut self.variants, other.variants);
+        if other.variants_from.is_some() {
+            self.variants_from = other.variants_from;
+        }
+        for step in other.steps.into_iter() {
+            if !self.steps.contains(&step) {
+                self.steps.push(step);
+            }
+        }
+        if other.target.is_some() {
+            self.target = other.target;
+        }
+        for item in other.checklist.into_iter() {
+            if !self.checklist.contains(&item) {
+                self.checklist.push(item);
+            }
+        }
+        if other.duration_days.unwrap_or(0) != 0 {
+            self.duration_days = other.duration_days;
+        }
+        if other.spoons_per_day {
+            self.spoons_per_day = true;
+        }
     }
 
     pub fn update(&mut self, other: TaskBuilder) {
@@ -129,6 +299,9 @@ impl TaskConfig {
         if let Some(weight) = other.weight {
             self.weight = weight;
         }
+        if let Some(spoons) = other.spoons {
+            self.spoons = spoons;
+        }
         if let Some(min_frequency) = other.min_frequency {
             self.min_frequency = min_frequency;
         }
@@ -145,6 +318,81 @@ impl TaskConfig {
                 }
             }
         }
+        if let Some(groups) = other.exclusion_groups {
+            for group in groups.into_iter() {
+                if !self.exclusion_groups.contains(&group) {
+                    self.exclusion_groups.push(group);
+                }
+            }
+        }
+        if let Some(requires) = other.requires {
+            self.requires.extend(requires);
+        }
+        if let Some(weekdays) = other.weekdays {
+            for weekday in weekdays.into_iter() {
+                if !self.weekdays.contains(&weekday) {
+                    self.weekdays.push(weekday);
+                }
+            }
+        }
+        if let Some(available_from) = other.available_from {
+            self.available_from = available_from;
+        }
+        if let Some(available_until) = other.available_until {
+            self.available_until = available_until;
+        }
+        if let Some(recurrence) = other.recurrence {
+            self.recurrence = recurrence;
+        }
+        if let Some(pinned) = other.pinned {
+            self.pinned = pinned;
+        }
+        if let Some(variants) = other.variants {
+            merge_variants(&mut self.variants, variants);
+        }
+        if let Some(variants_from) = other.variants_from {
+            self.variants_from = variants_from;
+        }
+        if let Some(steps) = other.steps {
+            for step in steps.into_iter() {
+                if !self.steps.contains(&step) {
+                    self.steps.push(step);
+                }
+            }
+        }
+        if let Some(target) = other.target {
+            self.target = target;
+        }
+        if let Some(checklist) = other.checklist {
+            for item in checklist.into_iter() {
+                if !self.checklist.contains(&item) {
+                    self.checklist.push(item);
+                }
+            }
+        }
+        if let Some(duration_days) = other.duration_days {
+            self.duration_days = duration_days;
+        }
+        if let Some(spoons_per_day) = other.spoons_per_day {
+            self.spoons_per_day = spoons_per_day;
+        }
+    }
+}
+
+/// Adds any new variants. Variants that already exist only have their weight and spoons
+/// overridden if they're set.
+fn merge_variants(variants: &mut Vec<TaskVariant>, others: Vec<TaskVariant>) {
+    for other in others.into_iter() {
+        if let Some(variant) = variants.iter_mut().find(|v| v.name == other.name) {
+            if other.weight.is_some() {
+                variant.weight = other.weight;
+            }
+            if other.spoons.is_some() {
+                variant.spoons = other.spoons;
+            }
+        } else {
+            variants.push(other);
+        }
     }
 }
 
@@ -1,14 +1,27 @@
+use crate::{
+    Error, Result,
+    util::{MonthDay, days_elapsed},
+};
 use crate::{RcCell, config::DisabledOptions, state::State};
-use crate::{Result, util::days_elapsed};
+use rand::{RngCore, seq::IndexedRandom};
 use serde::Serialize;
-use time::{Date, OffsetDateTime};
+use std::{
+    cell::OnceCell,
+    collections::{BTreeMap, BTreeSet},
+};
+use time::{Date, OffsetDateTime, Weekday};
 
 mod config;
+mod recurrence;
 mod set;
 mod state;
+pub mod template;
+mod variant;
 pub use config::*;
+pub use recurrence::*;
 pub use set::*;
 pub use state::*;
+pub use variant::*;
 
 pub const DEFAULT_WEIGHT: f64 = 1.0;
 pub const DEFAULT_SPOONS: u16 = 3;
@@ -18,6 +31,9 @@ pub const DEFAULT_SPOONS: u16 = 3;
 pub enum TaskStatus {
     Disabled,
     Complete,
+    PartiallyComplete,
+    /// One of an earlier day's tasks that wasn't completed.
+    Incomplete,
     InProgress,
     Inactive,
 }
@@ -32,11 +48,58 @@ pub struct TaskInfo {
     #[serde(skip_serializing_if = "std::option::Option::is_none")]
     pub description: Option<String>,
     pub weight: f64,
+    #[serde(skip_serializing_if = "std::option::Option::is_none")]
+    pub learned_weight: Option<f64>,
+    pub effective_weight: f64,
     pub spoons: u16,
     #[serde(skip_serializing_if = "DisabledOptions::is_enabled")]
     pub disabled: DisabledOptions,
     #[serde(skip_serializing_if = "std::vec::Vec::is_empty")]
     pub tags: Vec<String>,
+    #[serde(skip_serializing_if = "std::collections::BTreeSet::is_empty")]
+    pub exclusion_groups: BTreeSet<String>,
+    #[serde(skip_serializing_if = "std::collections::BTreeMap::is_empty")]
+    pub requires: BTreeMap<String, u32>,
+    #[serde(
+        with = "crate::serializers::weekdays",
+        skip_serializing_if = "std::vec::Vec::is_empty"
+    )]
+    pub weekdays: Vec<Weekday>,
+    #[serde(skip_serializing_if = "std::option::Option::is_none")]
+    pub available_from: Option<MonthDay>,
+    #[serde(skip_serializing_if = "std::option::Option::is_none")]
+    pub available_until: Option<MonthDay>,
+    #[serde(skip_serializing_if = "std::option::Option::is_none")]
+    pub recurrence: Option<Recurrence>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub pinned: bool,
+    #[serde(skip_serializing_if = "std::option::Option::is_none")]
+    pub variant: Option<String>,
+    #[serde(skip_serializing_if = "std::vec::Vec::is_empty")]
+    pub variants: Vec<TaskVariant>,
+    #[serde(skip_serializing_if = "std::option::Option::is_none")]
+    pub variants_from: Option<VariantSource>,
+    #[serde(skip_serializing_if = "std::option::Option::is_none")]
+    pub step: Option<String>,
+    #[serde(skip_serializing_if = "std::vec::Vec::is_empty")]
+    pub steps: Vec<String>,
+    #[serde(skip_serializing_if = "std::option::Option::is_none")]
+    pub target: Option<Target>,
+    #[serde(skip_serializing_if = "std::option::Option::is_none")]
+    pub progress: Option<String>,
+    #[serde(skip_serializing_if = "std::vec::Vec::is_empty")]
+    pub checklist: Vec<String>,
+    #[serde(skip_serializing_if = "std::collections::BTreeSet::is_empty")]
+    pub checked: BTreeSet<String>,
+    #[serde(skip_serializing_if = "std::option::Option::is_none")]
+    pub duration_days: Option<u32>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub spoons_per_day: bool,
+    #[serde(skip_serializing_if = "std::option::Option::is_none")]
+    pub day: Option<String>,
+    /// This is filled in separately, since it needs the history.
+    #[serde(skip_serializing_if = "std::option::Option::is_none")]
+    pub streak: Option<u32>,
 }
 
 #[derive(Debug)]
@@ -44,6 +107,8 @@ pub struct Task {
     slug: String,
     pub(crate) config: RcCell<TaskConfig>,
     pub(crate) state: RcCell<TaskState>,
+    /// The variants read from the task's variants file, which is only read when needed.
+    file_variants: OnceCell<Vec<TaskVariant>>,
 }
 
 macro_rules! impl_task_config_getters {
@@ -65,6 +130,13 @@ impl_task_config_getters! {
    weight: f64,
    spoons: u16,
    tags: Vec<String>,
+   pinned: bool,
+   variants: Vec<TaskVariant>,
+   steps: Vec<String>,
+   target: Option<Target>,
+   checklist: Vec<String>,
+   duration_days: Option<u32>,
+   spoons_per_day: bool,
 }
 
 macro_rules! impl_task_state_getters {
@@ -82,7 +154,10 @@ macro_rules! impl_task_state_getters {
 impl_task_state_getters! {
     disabled_on: Option<Date>,
     last_chosen: Option<Date>,
+    times_completed: u32,
+    times_chosen: u32,
     completed: bool,
+    variant: Option<String>,
 }
 
 impl Task {
@@ -92,6 +167,7 @@ impl Task {
             slug,
             config,
             state,
+            file_variants: OnceCell::new(),
         }
     }
     pub(crate) fn new(config: TaskConfig, state: TaskState) -> Self {
@@ -99,6 +175,7 @@ impl Task {
             slug: String::from(config.slug()),
             config: RcCell::new(config),
             state: RcCell::new(state),
+            file_variants: OnceCell::new(),
         }
     }
 
@@ -107,7 +184,99 @@ impl Task {
     }
 
     pub fn complete(&self) {
-        self.state.borrow_mut().complete();
+        let rendered = self.is_template().then(|| self.rendered_task());
+        let consume = self
+            .config
+            .borrow()
+            .variants_from
+            .as_ref()
+            .is_some_and(|s| s.consume);
+        let has_steps = !self.config.borrow().steps.is_empty();
+        let target = self.target();
+        let mut state = self.state.borrow_mut();
+        if has_steps && !state.completed {
+            state.step += 1;
+        }
+        if let Some(target) = target {
+            state.progress = state.progress.max(target.amount);
+        }
+        state.complete();
+        if consume
+            && state.variant_from_file
+            && let Some(variant) = state.variant.clone()
+        {
+            state.consumed.insert(variant);
+        }
+        if rendered.is_some() {
+            state.last_completed_as = rendered;
+        }
+    }
+
+    /// Completes the task on an earlier day that it was picked on. Only its stats and steps are
+    /// updated, since the rest of its state is for the day it was last picked.
+    pub fn complete_earlier(&self) {
+        let has_steps = !self.config.borrow().steps.is_empty();
+        let mut state = self.state.borrow_mut();
+        if has_steps {
+            state.step += 1;
+        }
+        state.complete_earlier();
+    }
+
+    /// Logs an amount towards the task's target, completing the task once the target is met.
+    /// Tasks without a target are completed straight away.
+    pub fn log_amount(&self, amount: f64) {
+        let Some(target) = self.target() else {
+            return self.complete();
+        };
+        let met = {
+            let mut state = self.state.borrow_mut();
+            state.progress += amount;
+            !state.completed && state.progress >= target.amount
+        };
+        if met {
+            self.complete();
+        }
+    }
+
+    /// Checks or unchecks an item in the task's checklist, which can be given by its name or its
+    /// position (starting from 1). The task is completed once every item is checked.
+    pub fn check(&self, item: &str, checked: bool) -> Result<()> {
+        let checklist = self.checklist();
+        let name = checklist
+            .iter()
+            .find(|i| i.as_str() == item)
+            .or_else(|| checklist.iter().find(|i| i.eq_ignore_ascii_case(item)))
+            .or_else(|| {
+                item.parse::<usize>()
+                    .ok()
+                    .and_then(|n| n.checked_sub(1))
+                    .and_then(|n| checklist.get(n))
+            })
+            .ok_or_else(|| Error::checklist_item_not_found(&self.slug, item))?;
+        let all_checked = {
+            let mut state = self.state.borrow_mut();
+            if checked {
+                state.checked.insert(name.clone());
+            } else {
+                state.checked.remove(name);
+            }
+            !state.completed && checklist.iter().all(|i| state.checked.contains(i))
+        };
+        if all_checked {
+            self.complete();
+        }
+        Ok(())
+    }
+
+    /// Describes how much has been logged towards the task's target today, such as `15/20 reps`.
+    pub fn target_progress(&self) -> Option<String> {
+        let target = self.target()?;
+        let progress = self.state.borrow().progress;
+        Some(match target.unit {
+            Some(unit) => format!("{progress}/{} {unit}", target.amount),
+            None => format!("{progress}/{}", target.amount),
+        })
     }
 
     pub fn enable(&self) {
@@ -120,10 +289,223 @@ impl Task {
         self.config.borrow_mut().disable();
     }
 
+    pub fn pin(&self) {
+        self.config.borrow_mut().pin();
+    }
+
+    pub fn unpin(&self) {
+        self.config.borrow_mut().unpin();
+    }
+
     pub fn choose(&self, state: &State) {
         self.state.borrow_mut().choose(state);
     }
 
+    /// Rolls new values for any placeholders in the task's title and description.
+    pub fn roll(&self, rng: &mut dyn RngCore) {
+        let config = self.config.borrow();
+        let mut rolled = template::roll(&config.task, rng);
+        if let Some(description) = config.description.as_ref() {
+            rolled.extend(template::roll(description, rng));
+        }
+        self.state.borrow_mut().rolled = rolled;
+    }
+
+    /// The variants read from the task's variants file, if it has one. The file is only read once.
+    pub fn file_variants(&self) -> Result<&[TaskVariant]> {
+        if let Some(variants) = self.file_variants.get() {
+            return Ok(variants);
+        }
+        let variants = match self.config.borrow().variants_from.as_ref() {
+            Some(source) => source.load()?,
+            None => Vec::new(),
+        };
+        Ok(self.file_variants.get_or_init(|| variants))
+    }
+
+    /// The variants that can still be chosen, along with whether they came from the variants file.
+    fn available_variants(&self) -> Result<Vec<(bool, TaskVariant)>> {
+        let config = self.config.borrow();
+        let state = self.state.borrow();
+        let consume = config.variants_from.as_ref().is_some_and(|s| s.consume);
+        let mut variants: Vec<_> = config.variants.iter().map(|v| (false, v.clone())).collect();
+        variants.extend(
+            self.file_variants()?
+                .iter()
+                .filter(|v| !(consume && state.consumed.contains(&v.name)))
+                .map(|v| (true, v.clone())),
+        );
+        Ok(variants)
+    }
+
+    /// Returns `true` if the task has variants, including any in its variants file.
+    pub fn has_variants(&self) -> bool {
+        let config = self.config.borrow();
+        !config.variants.is_empty() || config.variants_from.is_some()
+    }
+
+    /// Returns `false` if the task only has variants from a file and they have all been used up
+    /// or the file can't be read.
+    pub fn has_variants_left(&self) -> bool {
+        if !self.has_variants() {
+            return true;
+        }
+        match self.available_variants() {
+            Ok(variants) => !variants.is_empty(),
+            Err(error) => {
+                log::warn!("Unable to read the variants for {}: {error}", self.slug);
+                false
+            }
+        }
+    }
+
+    /// Chooses one of the task's variants, favouring variants with higher weights. If a spoon
+    /// budget is given, only variants that fit into it are considered unless none of them do.
+    pub fn pick_variant(&self, max_spoons: Option<u16>, rng: &mut dyn RngCore) -> Result<()> {
+        let variants = self.available_variants()?;
+        let chosen = if variants.is_empty() {
+            None
+        } else {
+            let spoons = self.spoons();
+            let fits: Vec<_> = variants
+                .iter()
+                .filter(|(_, v)| max_spoons.is_none_or(|max| v.spoons.unwrap_or(spoons) <= max))
+                .collect();
+            let variants = if fits.is_empty() {
+                variants.iter().collect()
+            } else {
+                fits
+            };
+            let (from_file, variant) =
+                variants.choose_weighted(rng, |(_, v)| v.weight.unwrap_or(DEFAULT_WEIGHT))?;
+            log::debug!("Picked variant {} of {}", variant.name, self.slug);
+            Some((*from_file, variant.name.clone()))
+        };
+        let mut state = self.state.borrow_mut();
+        state.variant_from_file = chosen.as_ref().is_some_and(|(from_file, _)| *from_file);
+        state.variant = chosen.map(|(_, name)| name);
+        Ok(())
+    }
+
+    /// The variant chosen when the task was last picked, if it still exists.
+    pub fn current_variant(&self) -> Option<TaskVariant> {
+        let state = self.state.borrow();
+        let name = state.variant.as_ref()?;
+        if state.variant_from_file {
+            return self
+                .file_variants()
+                .ok()?
+                .iter()
+                .find(|v| &v.name == name)
+                .cloned();
+        }
+        self.config
+            .borrow()
+            .variants
+            .iter()
+            .find(|v| &v.name == name)
+            .cloned()
+    }
+
+    /// The number of spoons the task takes, taking its current variant into account.
+    pub fn current_spoons(&self) -> u16 {
+        self.current_variant()
+            .and_then(|v| v.spoons)
+            .unwrap_or_else(|| self.spoons())
+    }
+
+    /// The number of spoons the task takes out of today's budget. Multi-day tasks only take
+    /// spoons on the day they were picked, unless they're configured to take them every day.
+    pub fn todays_spoons(&self, state: &State) -> u16 {
+        if self.spoons_per_day() || self.last_chosen() == Some(state.todays_date()) {
+            self.current_spoons()
+        } else {
+            0
+        }
+    }
+
+    /// Which day of a multi-day task today is, starting from 1. Returns `None` for tasks that
+    /// only last a day or haven't been picked.
+    pub fn day_of(&self, state: &State) -> Option<u32> {
+        let duration = self.duration_days().filter(|d| *d > 1)?;
+        let day = state.days_since_today(self.last_chosen()?) + 1;
+        (1..=duration as i64).contains(&day).then_some(day as u32)
+    }
+
+    /// Returns `true` if the task was picked on an earlier day and should stay part of today's
+    /// tasks, because it hasn't been completed and its window hasn't expired.
+    pub fn carries_over(&self, state: &State) -> bool {
+        !self.completed() && !self.disabled(state) && self.day_of(state).is_some_and(|day| day > 1)
+    }
+
+    /// The fewest number of spoons the task can take, depending on which variant is chosen.
+    pub fn min_spoons(&self) -> u16 {
+        let spoons = self.spoons();
+        self.available_variants()
+            .unwrap_or_default()
+            .iter()
+            .map(|(_, v)| v.spoons.unwrap_or(spoons))
+            .min()
+            .unwrap_or(spoons)
+    }
+
+    /// Returns `true` if the task has steps and all of them have been completed.
+    pub fn retired(&self) -> bool {
+        let steps = self.config.borrow().steps.len();
+        steps > 0 && self.state.borrow().step >= steps
+    }
+
+    /// The position of the task's current step, if it has steps. If the task has been completed
+    /// today, then this is the step it was completed with.
+    pub fn current_step(&self, state: &State) -> Option<usize> {
+        if self.config.borrow().steps.is_empty() {
+            return None;
+        }
+        let task_state = self.state.borrow();
+        if task_state.completed && state.todays_tasks().contains(&self.slug) {
+            task_state.step.checked_sub(1)
+        } else {
+            Some(task_state.step)
+        }
+    }
+
+    /// Describes the task's current step along with how far through the steps it is, such as
+    /// `Lesson 3 (3/10)`.
+    pub fn step_progress(&self, state: &State) -> Option<String> {
+        let position = self.current_step(state)?;
+        let steps = self.steps();
+        steps
+            .get(position)
+            .map(|step| format!("{step} ({}/{})", position + 1, steps.len()))
+    }
+
+    /// Returns `true` if the task's title or description have any placeholders.
+    pub fn is_template(&self) -> bool {
+        let config = self.config.borrow();
+        template::is_template(&config.task)
+            || config
+                .description
+                .as_ref()
+                .is_some_and(|d| template::is_template(d))
+    }
+
+    /// The task's title, with any placeholders replaced by the values rolled when it was picked.
+    pub fn rendered_task(&self) -> String {
+        template::render(&self.config.borrow().task, &self.state.borrow().rolled)
+    }
+
+    /// The task's description, with any placeholders replaced by the values rolled when it was
+    /// picked.
+    pub fn rendered_description(&self) -> Option<String> {
+        let config = self.config.borrow();
+        let state = self.state.borrow();
+        let skip = template::count_placeholders(&config.task).min(state.rolled.len());
+        config
+            .description
+            .as_ref()
+            .map(|d| template::render(d, &state.rolled[skip..]))
+    }
+
     pub fn slug(&self) -> &str {
         &self.slug
     }
@@ -134,24 +516,88 @@ impl Task {
 
     pub fn info(&self, state: &State) -> TaskInfo {
         let config = self.config.borrow();
+        let is_today = state.todays_tasks().contains(&self.slug);
+        // Only today's tasks have placeholders that have been rolled.
+        let (task, description) = if is_today {
+            (self.rendered_task(), self.rendered_description())
+        } else {
+            (config.task.clone(), config.description.clone())
+        };
         TaskInfo {
             slug: self.slug.clone(),
             status: self.status(state),
-            task: config.task.clone(),
-            description: config.description.clone(),
+            task,
+            description,
             disabled: config.disabled.clone(),
             tags: config.tags.clone(),
+            exclusion_groups: state.config().exclusion_groups_of(&self.slug),
+            requires: config.requires.clone(),
+            weekdays: config.weekdays.clone(),
+            available_from: config.available_from,
+            available_until: config.available_until,
+            recurrence: config.recurrence.clone(),
+            pinned: config.pinned,
+            variant: self.current_variant().map(|v| v.name).filter(|_| is_today),
+            variants: config.variants.clone(),
+            variants_from: config.variants_from.clone(),
+            step: self.step_progress(state),
+            steps: config.steps.clone(),
+            target: config.target.clone(),
+            progress: self.target_progress().filter(|_| is_today),
+            checklist: config.checklist.clone(),
+            duration_days: config.duration_days,
+            spoons_per_day: config.spoons_per_day,
+            day: self
+                .day_of(state)
+                .filter(|_| is_today)
+                .map(|day| format!("{day}/{}", config.duration_days.unwrap_or(1))),
+            streak: None,
+            checked: if is_today {
+                self.state.borrow().checked.clone()
+            } else {
+                BTreeSet::new()
+            },
             weight: config.weight,
+            learned_weight: self.learned_weight(state),
+            effective_weight: self.effective_weight(state),
             spoons: config.spoons,
         }
     }
 
+    /// The task's weight after being adjusted by its completion rate, if adaptive weights are
+    /// enabled. Tasks that have never been chosen are not adjusted.
+    pub fn learned_weight(&self, state: &State) -> Option<f64> {
+        let adaptive = state.config().adaptive().as_ref()?;
+        let weight = self.config.borrow().weight;
+        Some(match self.state.borrow().completion_rate() {
+            Some(rate) => weight * adaptive.multiplier(rate),
+            None => weight,
+        })
+    }
+
+    /// The weight used when picking the task, after any adjustments and boosts have been applied.
+    pub fn effective_weight(&self, state: &State) -> f64 {
+        let weight = self
+            .learned_weight(state)
+            .unwrap_or_else(|| self.config.borrow().weight);
+        match (state.config().staleness(), self.last_chosen()) {
+            (Some(staleness), Some(last_chosen)) => {
+                weight * staleness.multiplier(state.days_since_today(last_chosen))
+            }
+            (Some(staleness), None) => weight * staleness.unchosen_multiplier(),
+            (None, _) => weight,
+        }
+    }
+
     pub fn status(&self, state: &State) -> TaskStatus {
         if self.disabled(state) {
             TaskStatus::Disabled
         } else if state.todays_tasks().contains(&self.slug) {
-            if self.state.borrow().completed {
+            let task_state = self.state.borrow();
+            if task_state.completed {
                 TaskStatus::Complete
+            } else if !task_state.checked.is_empty() || task_state.progress > 0.0 {
+                TaskStatus::PartiallyComplete
             } else {
                 TaskStatus::InProgress
             }
@@ -173,11 +619,54 @@ impl Task {
         }
     }
 
-    /// Returns `true` if the task can be chosen today.
+    /// Returns `true` if the task's recurrence rule fires on the given date.
+    pub fn scheduled_on(&self, date: Date) -> bool {
+        self.config
+            .borrow()
+            .recurrence
+            .as_ref()
+            .is_some_and(|r| r.fires_on(date))
+    }
+
+    /// Returns `true` if the task has to be part of the tasks on the given date, either because
+    /// it's pinned or because it's scheduled.
+    pub fn forced_on(&self, date: Date) -> bool {
+        self.pinned() || self.scheduled_on(date)
+    }
+
+    /// Returns `true` if the task is one of today's tasks but shouldn't count towards today's
+    /// limit.
+    pub fn exempt_from_limit(&self, state: &State) -> bool {
+        (state.config().exempt_scheduled_tasks() && self.scheduled_on(state.todays_date()))
+            || (state.config().exempt_pinned_tasks() && self.pinned())
+    }
+
+    /// Returns `true` if every task this task requires has been completed enough times.
+    pub fn requirements_met(&self, state: &State) -> bool {
+        self.config.borrow().requires.iter().all(|(slug, times)| {
+            state
+                .get_task(slug)
+                .is_some_and(|t| t.times_completed() >= *times)
+        })
+    }
+
+    /// Returns `true` if the task can be chosen today. Tasks with a recurrence rule can only be
+    /// chosen on the days it fires.
     pub fn choosable(&self, the_state: &State) -> bool {
         let config = self.config.borrow();
         let state = self.state.borrow();
-        if !(self.disabled(the_state) || the_state.todays_tasks().contains(&self.slug)) {
+        if self.disabled(the_state)
+            || the_state.todays_tasks().contains(&self.slug)
+            || the_state
+                .todays_tasks()
+                .iter()
+                .any(|other| the_state.config().mutually_exclusive(&self.slug, other))
+            || !self.requirements_met(the_state)
+            || !self.has_variants_left()
+            || self.retired()
+            || !config.available_on(the_state.todays_date())
+            || (config.recurrence.is_some() && !self.scheduled_on(the_state.todays_date()))
+        {
             false
         } else if let Some(max_occurrences) = config.max_occurrences
             && state.times_completed >= max_occurrences
@@ -0,0 +1,386 @@
+use crate::{Error, Result, util::days_elapsed};
+use serde::{Deserialize, Serialize};
+use std::{fmt, str::FromStr};
+use strum::{EnumIs, EnumString};
+use time::{Date, Duration, Month, Weekday, util::days_in_year};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIs, EnumString)]
+#[strum(serialize_all = "UPPERCASE")]
+pub enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+/// A weekday in a `BYDAY` rule, optionally limited to the nth occurrence of it in the month or
+/// year (negative values count from the end).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ByDay {
+    pub nth: Option<i8>,
+    pub weekday: Weekday,
+}
+
+/// A subset of iCalendar's recurrence rules (RFC 5545), such as `FREQ=MONTHLY;BYDAY=2TU` for
+/// the second Tuesday of every month. The supported parts are `FREQ`, `INTERVAL`, `UNTIL`,
+/// `BYDAY`, `BYMONTHDAY`, and `BYMONTH`, along with a `DTSTART` part that anchors intervals.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Recurrence {
+    rule: String,
+    pub freq: Frequency,
+    pub interval: u32,
+    pub start: Option<Date>,
+    pub until: Option<Date>,
+    pub by_day: Vec<ByDay>,
+    pub by_month_day: Vec<i8>,
+    pub by_month: Vec<Month>,
+}
+
+fn parse_rule_date(rule: &str, value: &str) -> Result<Date> {
+    let digits: String = value.chars().filter(char::is_ascii_digit).take(8).collect();
+    let invalid = || Error::invalid_recurrence(rule, format!("'{value}' is not a valid date"));
+    if digits.len() != 8 {
+        return Err(invalid());
+    }
+    let year = digits[..4].parse().map_err(|_| invalid())?;
+    let month = digits[4..6]
+        .parse::<u8>()
+        .ok()
+        .and_then(|m| Month::try_from(m).ok())
+        .ok_or_else(invalid)?;
+    let day = digits[6..].parse().map_err(|_| invalid())?;
+    Date::from_calendar_date(year, month, day).map_err(|_| invalid())
+}
+
+fn parse_by_day(rule: &str, value: &str) -> Result<ByDay> {
+    let invalid = || Error::invalid_recurrence(rule, format!("'{value}' is not a valid day"));
+    let split = value.len().checked_sub(2).ok_or_else(invalid)?;
+    let (nth, weekday) = value.split_at(split);
+    let weekday = match weekday {
+        "MO" => Weekday::Monday,
+        "TU" => Weekday::Tuesday,
+        "WE" => Weekday::Wednesday,
+        "TH" => Weekday::Thursday,
+        "FR" => Weekday::Friday,
+        "SA" => Weekday::Saturday,
+        "SU" => Weekday::Sunday,
+        _ => return Err(invalid()),
+    };
+    let nth = if nth.is_empty() {
+        None
+    } else {
+        Some(
+            nth.parse::<i8>()
+                .ok()
+                .filter(|n| *n != 0)
+                .ok_or_else(invalid)?,
+        )
+    };
+    Ok(ByDay { nth, weekday })
+}
+
+fn parse_list<T, F>(value: &str, parse: F) -> Result<Vec<T>>
+where
+    F: Fn(&str) -> Result<T>,
+{
+    value.split(',').map(parse).collect()
+}
+
+/// Returns the Monday of the week the date falls in.
+#[inline]
+fn start_of_week(date: Date) -> Date {
+    date - Duration::days(date.weekday().number_days_from_monday() as i64)
+}
+
+#[inline]
+fn months_between(from: Date, to: Date) -> i64 {
+    (to.year() as i64 * 12 + to.month() as i64) - (from.year() as i64 * 12 + from.month() as i64)
+}
+
+impl Recurrence {
+    /// Returns `true` if the rule fires on the given date.
+    pub fn fires_on(&self, date: Date) -> bool {
+        if self.start.is_some_and(|s| date < s) || self.until.is_some_and(|u| date > u) {
+            return false;
+        }
+        self.in_interval(date)
+            && (self.by_month.is_empty() || self.by_month.contains(&date.month()))
+            && (self.by_month_day.is_empty()
+                || self
+                    .by_month_day
+                    .iter()
+                    .any(|d| Self::is_month_day(date, *d)))
+            && (self.by_day.is_empty() || self.by_day.iter().any(|d| self.is_by_day(date, d)))
+            && self.matches_start(date)
+    }
+
+    /// Returns `true` if the date falls within a period that's a multiple of the interval from
+    /// the start.
+    fn in_interval(&self, date: Date) -> bool {
+        let Some(start) = self.start else {
+            return true;
+        };
+        let periods = match self.freq {
+            Frequency::Daily => days_elapsed(date, start),
+            Frequency::Weekly => days_elapsed(start_of_week(date), start_of_week(start)) / 7,
+            Frequency::Monthly => months_between(start, date),
+            Frequency::Yearly => (date.year() - start.year()) as i64,
+        };
+        periods % self.interval as i64 == 0
+    }
+
+    /// If a rule has no `BY*` parts, then it fires on the same day as its start.
+    fn matches_start(&self, date: Date) -> bool {
+        if !(self.by_day.is_empty() && self.by_month_day.is_empty()) {
+            return true;
+        }
+        match (self.freq, self.start) {
+            (Frequency::Weekly, Some(start)) => date.weekday() == start.weekday(),
+            (Frequency::Monthly, Some(start)) => date.day() == start.day(),
+            (Frequency::Yearly, Some(start)) => {
+                date.day() == start.day()
+                    && (!self.by_month.is_empty() || date.month() == start.month())
+            }
+            _ => true,
+        }
+    }
+
+    fn is_month_day(date: Date, day: i8) -> bool {
+        let length = date.month().length(date.year()) as i8;
+        if day > 0 {
+            date.day() as i8 == day
+        } else {
+            date.day() as i8 == length + day + 1
+        }
+    }
+
+    fn is_by_day(&self, date: Date, by_day: &ByDay) -> bool {
+        if date.weekday() != by_day.weekday {
+            return false;
+        }
+        let Some(nth) = by_day.nth else {
+            return true;
+        };
+        // Yearly rules without any months count weekdays from the start of the year.
+        let (position, length) = if self.freq.is_yearly() && self.by_month.is_empty() {
+            (date.ordinal() as i16, days_in_year(date.year()) as i16)
+        } else {
+            (date.day() as i16, date.month().length(date.year()) as i16)
+        };
+        if nth > 0 {
+            (position - 1) / 7 + 1 == nth as i16
+        } else {
+            -((length - position) / 7 + 1) == nth as i16
+        }
+    }
+}
+
+impl FromStr for Recurrence {
+    type Err = Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        let rule = value.trim();
+        let mut freq = None;
+        let mut recurrence = Self {
+            rule: String::from(rule),
+            freq: Frequency::Daily,
+            interval: 1,
+            start: None,
+            until: None,
+            by_day: Vec::new(),
+            by_month_day: Vec::new(),
+            by_month: Vec::new(),
+        };
+        let parts = rule.strip_prefix("RRULE:").unwrap_or(rule);
+        for part in parts.split(';').filter(|p| !p.is_empty()) {
+            let (key, value) = part
+                .split_once('=')
+                .ok_or_else(|| Error::invalid_recurrence(rule, format!("'{part}' has no value")))?;
+            let value = value.to_uppercase();
+            match key.to_uppercase().as_str() {
+                "FREQ" => {
+                    freq = Some(value.parse().map_err(|_| {
+                        Error::invalid_recurrence(rule, format!("'{value}' is not supported"))
+                    })?)
+                }
+                "INTERVAL" => {
+                    recurrence.interval =
+                        value.parse().ok().filter(|i| *i > 0).ok_or_else(|| {
+                            Error::invalid_recurrence(rule, "INTERVAL must be a positive number")
+                        })?
+                }
+                "DTSTART" => recurrence.start = Some(parse_rule_date(rule, &value)?),
+                "UNTIL" => recurrence.until = Some(parse_rule_date(rule, &value)?),
+                "BYDAY" => recurrence.by_day = parse_list(&value, |d| parse_by_day(rule, d))?,
+                "BYMONTHDAY" => {
+                    recurrence.by_month_day = parse_list(&value, |d| {
+                        d.parse::<i8>()
+                            .ok()
+                            .filter(|d| *d != 0 && (-31..=31).contains(d))
+                            .ok_or_else(|| {
+                                Error::invalid_recurrence(rule, format!("'{d}' is not a valid day"))
+                            })
+                    })?
+                }
+                "BYMONTH" => {
+                    recurrence.by_month = parse_list(&value, |m| {
+                        m.parse::<u8>()
+                            .ok()
+                            .and_then(|m| Month::try_from(m).ok())
+                            .ok_or_else(|| {
+                                Error::invalid_recurrence(
+                                    rule,
+                                    format!("'{m}' is not a valid month"),
+                                )
+                            })
+                    })?
+                }
+                _ => {
+                    return Err(Error::invalid_recurrence(
+                        rule,
+                        format!("'{key}' is not supported"),
+                    ));
+                }
+            }
+        }
+        recurrence.freq =
+            freq.ok_or_else(|| Error::invalid_recurrence(rule, "FREQ is required"))?;
+        if recurrence.start.is_none() {
+            if recurrence.interval > 1 {
+                return Err(Error::invalid_recurrence(
+                    rule,
+                    "DTSTART is required when INTERVAL is more than 1",
+                ));
+            }
+            if !recurrence.freq.is_daily()
+                && recurrence.by_day.is_empty()
+                && recurrence.by_month_day.is_empty()
+            {
+                return Err(Error::invalid_recurrence(
+                    rule,
+                    "either DTSTART, BYDAY, or BYMONTHDAY is required",
+                ));
+            }
+        }
+        Ok(recurrence)
+    }
+}
+
+impl TryFrom<String> for Recurrence {
+    type Error = Error;
+
+    fn try_from(value: String) -> Result<Self> {
+        value.parse()
+    }
+}
+
+impl From<Recurrence> for String {
+    fn from(value: Recurrence) -> Self {
+        value.rule
+    }
+}
+
+impl fmt::Display for Recurrence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.rule)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use time::macros::date;
+
+    fn rule(value: &str) -> Recurrence {
+        value.parse().unwrap()
+    }
+
+    #[test]
+    fn last_weekday_of_month() {
+        let recurrence = rule("FREQ=MONTHLY;BYDAY=-1FR");
+        assert!(recurrence.fires_on(date!(2026 - 10 - 30)));
+        assert!(!recurrence.fires_on(date!(2026 - 10 - 23)));
+        assert!(recurrence.fires_on(date!(2026 - 11 - 27)));
+    }
+
+    #[test]
+    fn nth_weekday_of_month() {
+        let recurrence = rule("FREQ=YEARLY;BYMONTH=11;BYDAY=4TH");
+        assert!(recurrence.fires_on(date!(2026 - 11 - 26)));
+        assert!(!recurrence.fires_on(date!(2026 - 11 - 19)));
+        assert!(!recurrence.fires_on(date!(2026 - 10 - 22)));
+    }
+
+    #[test]
+    fn yearly_weekday_counts_from_start_of_year() {
+        let recurrence = rule("FREQ=YEARLY;BYDAY=20MO");
+        assert!(recurrence.fires_on(date!(2026 - 05 - 18)));
+        assert!(!recurrence.fires_on(date!(2026 - 05 - 11)));
+        let recurrence = rule("FREQ=YEARLY;BYDAY=-1MO");
+        assert!(recurrence.fires_on(date!(2026 - 12 - 28)));
+    }
+
+    #[test]
+    fn interval_is_counted_from_start() {
+        let recurrence = rule("FREQ=WEEKLY;INTERVAL=2;DTSTART=20261005;BYDAY=MO");
+        assert!(!recurrence.fires_on(date!(2026 - 09 - 28)));
+        assert!(recurrence.fires_on(date!(2026 - 10 - 05)));
+        assert!(!recurrence.fires_on(date!(2026 - 10 - 12)));
+        assert!(recurrence.fires_on(date!(2026 - 10 - 19)));
+
+        let recurrence = rule("FREQ=DAILY;INTERVAL=3;DTSTART=20261001");
+        assert!(recurrence.fires_on(date!(2026 - 10 - 04)));
+        assert!(!recurrence.fires_on(date!(2026 - 10 - 05)));
+    }
+
+    #[test]
+    fn start_is_used_without_by_parts() {
+        let recurrence = rule("FREQ=MONTHLY;DTSTART=20260115");
+        assert!(recurrence.fires_on(date!(2026 - 03 - 15)));
+        assert!(!recurrence.fires_on(date!(2026 - 03 - 16)));
+    }
+
+    #[test]
+    fn negative_month_day() {
+        let recurrence = rule("FREQ=MONTHLY;BYMONTHDAY=-1");
+        assert!(recurrence.fires_on(date!(2026 - 02 - 28)));
+        assert!(recurrence.fires_on(date!(2028 - 02 - 29)));
+        assert!(!recurrence.fires_on(date!(2028 - 02 - 28)));
+    }
+
+    #[test]
+    fn until_is_inclusive() {
+        let recurrence = rule("RRULE:FREQ=DAILY;UNTIL=20261031");
+        assert!(recurrence.fires_on(date!(2026 - 10 - 31)));
+        assert!(!recurrence.fires_on(date!(2026 - 11 - 01)));
+    }
+
+    #[test]
+    fn invalid_rules() {
+        for value in [
+            "BYDAY=MO",
+            "FREQ=HOURLY",
+            "FREQ=DAILY;INTERVAL=2",
+            "FREQ=DAILY;INTERVAL=0;DTSTART=20261001",
+            "FREQ=WEEKLY",
+            "FREQ=MONTHLY;BYDAY=0MO",
+            "FREQ=MONTHLY;BYMONTHDAY=32",
+            "FREQ=YEARLY;BYMONTH=13;BYMONTHDAY=1",
+            "FREQ=DAILY;DTSTART=2026",
+            "FREQ=DAILY;COUNT=3",
+        ] {
+            assert!(
+                value.parse::<Recurrence>().is_err(),
+                "{value} should be invalid"
+            );
+        }
+    }
+
+    #[test]
+    fn displays_original_rule() {
+        let value = "FREQ=MONTHLY;BYDAY=2TU";
+        assert_eq!(rule(value).to_string(), value);
+    }
+}
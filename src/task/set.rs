@@ -21,6 +21,11 @@ impl TaskSet {
         Self::default()
     }
 
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
     pub fn resolve<'a>(&self, state: &'a State) -> Result<Vec<&'a Task>> {
         self.0
             .iter()
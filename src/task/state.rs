@@ -1,5 +1,6 @@
-use crate::{state::State, util::today};
+use crate::{config::DEFAULT_ADAPTIVE_DECAY, state::State, util::today};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
 use time::{Date, OffsetDateTime};
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -11,16 +12,61 @@ pub struct TaskState {
     pub last_chosen: Option<Date>,
     #[serde(default)]
     pub times_completed: u32,
+    #[serde(default)]
+    pub times_chosen: u32,
+    /// How many times the task has been chosen, with older choices decayed.
+    #[serde(default)]
+    pub recent_chosen: f64,
+    /// How many times the task has been completed after being chosen, with older completions
+    /// decayed.
+    #[serde(default)]
+    pub recent_completed: f64,
     pub completed: bool,
+    /// The values rolled for the task's placeholders when it was last chosen.
+    #[serde(default, skip_serializing_if = "std::vec::Vec::is_empty")]
+    pub rolled: Vec<String>,
+    /// What the task's title was, with its placeholders filled in, when it was last completed.
+    #[serde(default, skip_serializing_if = "std::option::Option::is_none")]
+    pub last_completed_as: Option<String>,
+    /// The name of the variant chosen when the task was last picked.
+    #[serde(default, skip_serializing_if = "std::option::Option::is_none")]
+    pub variant: Option<String>,
+    /// Whether the chosen variant came from the task's variants file.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub variant_from_file: bool,
+    /// The names of the variants in the task's variants file that have been used up, so that
+    /// they stay used up if the file is edited.
+    #[serde(default, skip_serializing_if = "std::collections::BTreeSet::is_empty")]
+    pub consumed: BTreeSet<String>,
+    /// How many of the task's steps have been completed.
+    #[serde(default, skip_serializing_if = "crate::util::is_zero")]
+    pub step: usize,
+    /// The amount logged towards the task's target today.
+    #[serde(default, skip_serializing_if = "crate::util::is_zero_f64")]
+    pub progress: f64,
+    /// The checklist items that have been checked today.
+    #[serde(default, skip_serializing_if = "std::collections::BTreeSet::is_empty")]
+    pub checked: BTreeSet<String>,
 }
 
 impl TaskState {
     pub fn reset(&mut self) {
         self.completed = false;
+        self.progress = 0.0;
+        self.checked.clear();
     }
 
     pub fn complete(&mut self) {
+        if !self.completed {
+            self.recent_completed += 1.0;
+            self.times_completed += 1;
+        }
         self.completed = true;
+    }
+
+    /// Counts a completion from an earlier day, without marking the task as completed today.
+    pub fn complete_earlier(&mut self) {
+        self.recent_completed += 1.0;
         self.times_completed += 1;
     }
 
@@ -34,7 +80,45 @@ impl TaskState {
     }
 
     pub fn choose(&mut self, state: &State) {
+        let decay = state
+            .config()
+            .adaptive()
+            .map(|a| a.decay)
+            .unwrap_or(DEFAULT_ADAPTIVE_DECAY);
         self.reset();
         self.last_chosen = Some(state.todays_date());
+        self.times_chosen += 1;
+        self.recent_chosen = self.recent_chosen * decay + 1.0;
+        self.recent_completed *= decay;
+    }
+
+    /// How often the task has been completed after being chosen, favouring recent behaviour.
+    /// Returns `None` if the task has never been chosen.
+    pub fn completion_rate(&self) -> Option<f64> {
+        if self.recent_chosen > 0.0 {
+            Some((self.recent_completed / self.recent_chosen).min(1.0))
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn completing_twice_counts_once() {
+        let mut state = TaskState::default();
+        state.complete();
+        state.complete();
+        assert!(state.completed);
+        assert_eq!(state.times_completed, 1);
+        assert_eq!(state.recent_completed, 1.0);
+
+        state.reset();
+        state.complete();
+        assert_eq!(state.times_completed, 2);
     }
 }
@@ -0,0 +1,161 @@
+use rand::{Rng, RngCore, seq::IndexedRandom};
+
+/// A value in a task's text that's rolled when the task is picked.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Placeholder {
+    /// A whole number between the two numbers (inclusive), such as `{3..6}`.
+    Range(i64, i64),
+    /// One of a list of values, such as `{20,30,45}`.
+    Choices(Vec<String>),
+}
+
+impl Placeholder {
+    fn parse(inner: &str) -> Option<Self> {
+        if let Some((start, end)) = inner.split_once("..")
+            && let (Ok(start), Ok(end)) = (start.trim().parse(), end.trim().parse())
+        {
+            Some(Self::Range(start, end))
+        } else if inner.contains(',') {
+            Some(Self::Choices(
+                inner.split(',').map(|c| String::from(c.trim())).collect(),
+            ))
+        } else {
+            None
+        }
+    }
+
+    pub fn roll(&self, rng: &mut dyn RngCore) -> String {
+        match self {
+            Self::Range(start, end) => rng
+                .random_range(*start.min(end)..=*start.max(end))
+                .to_string(),
+            Self::Choices(choices) => choices.choose(rng).cloned().unwrap_or_default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Part<'a> {
+    Text(&'a str),
+    Placeholder(Placeholder, &'a str),
+}
+
+/// Splits text into plain text and placeholders. Braces that don't contain a valid placeholder
+/// are treated as plain text.
+fn parse(text: &str) -> Vec<Part<'_>> {
+    let mut parts = Vec::new();
+    let mut rest = text;
+    while let Some(start) = rest.find('{') {
+        let Some(len) = rest[start..].find('}') else {
+            break;
+        };
+        let raw = &rest[start..=start + len];
+        if let Some(placeholder) = Placeholder::parse(&raw[1..raw.len() - 1]) {
+            parts.push(Part::Text(&rest[..start]));
+            parts.push(Part::Placeholder(placeholder, raw));
+        } else {
+            parts.push(Part::Text(&rest[..=start + len]));
+        }
+        rest = &rest[start + len + 1..];
+    }
+    parts.push(Part::Text(rest));
+    parts
+}
+
+/// Returns `true` if the text has any placeholders.
+#[inline]
+pub fn is_template(text: &str) -> bool {
+    count_placeholders(text) > 0
+}
+
+pub fn count_placeholders(text: &str) -> usize {
+    parse(text)
+        .iter()
+        .filter(|p| matches!(p, Part::Placeholder(..)))
+        .count()
+}
+
+/// Rolls a value for each of the text's placeholders, in order.
+pub fn roll(text: &str, rng: &mut dyn RngCore) -> Vec<String> {
+    parse(text)
+        .into_iter()
+        .filter_map(|p| match p {
+            Part::Placeholder(placeholder, _) => Some(placeholder.roll(rng)),
+            Part::Text(_) => None,
+        })
+        .collect()
+}
+
+/// Replaces the text's placeholders with the given values, in order. Any placeholders without a
+/// value are left as they are.
+pub fn render(text: &str, values: &[String]) -> String {
+    let mut values = values.iter();
+    parse(text)
+        .into_iter()
+        .map(|p| match p {
+            Part::Text(text) => text,
+            Part::Placeholder(_, raw) => values.next().map(String::as_str).unwrap_or(raw),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    #[test]
+    fn parses_placeholders() {
+        assert_eq!(Placeholder::parse("3..6"), Some(Placeholder::Range(3, 6)));
+        assert_eq!(
+            Placeholder::parse(" -2 .. 2 "),
+            Some(Placeholder::Range(-2, 2))
+        );
+        assert_eq!(
+            Placeholder::parse("20, 30,45"),
+            Some(Placeholder::Choices(vec![
+                String::from("20"),
+                String::from("30"),
+                String::from("45")
+            ]))
+        );
+        assert_eq!(Placeholder::parse("name"), None);
+        assert_eq!(Placeholder::parse("a..b"), None);
+    }
+
+    #[test]
+    fn counts_placeholders() {
+        assert_eq!(count_placeholders("Run {3..6} km"), 1);
+        assert_eq!(count_placeholders("{1..2} sets of {10,15} reps"), 2);
+        assert_eq!(count_placeholders("Plain {text} and { unclosed"), 0);
+        assert!(!is_template("No placeholders"));
+    }
+
+    #[test]
+    fn renders_values_in_order() {
+        let values = [String::from("2"), String::from("15")];
+        assert_eq!(
+            render("{1..2} sets of {10,15} reps", &values),
+            "2 sets of 15 reps"
+        );
+        assert_eq!(
+            render("{1..2} sets of {10,15} reps", &values[..1]),
+            "2 sets of {10,15} reps"
+        );
+        assert_eq!(render("Keep {braces} as {is", &[]), "Keep {braces} as {is");
+    }
+
+    #[test]
+    fn rolls_within_placeholders() {
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        for _ in 0..50 {
+            let values = roll("{6..3} or {a,b}", &mut rng);
+            assert_eq!(values.len(), 2);
+            let number: i64 = values[0].parse().unwrap();
+            assert!((3..=6).contains(&number));
+            assert!(values[1] == "a" || values[1] == "b");
+        }
+    }
+}
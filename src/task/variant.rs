@@ -0,0 +1,263 @@
+use crate::{CONFIG_FILE_PATH, Error, Result};
+use camino::{Utf8Path, Utf8PathBuf};
+use serde::{Deserialize, Serialize};
+use std::{convert::Infallible, fs, str::FromStr};
+
+/// A concrete option for a task, such as a specific recipe for a "cook something" task. Variants
+/// can be written as just their name.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", from = "VariantDef")]
+pub struct TaskVariant {
+    pub name: String,
+    /// How likely the variant is to be chosen. Defaults to [`super::DEFAULT_WEIGHT`].
+    #[serde(skip_serializing_if = "std::option::Option::is_none")]
+    pub weight: Option<f64>,
+    /// The number of spoons the variant takes. Defaults to the task's spoons.
+    #[serde(skip_serializing_if = "std::option::Option::is_none")]
+    pub spoons: Option<u16>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged, rename_all_fields = "kebab-case")]
+enum VariantDef {
+    Name(String),
+    Full {
+        name: String,
+        #[serde(default)]
+        weight: Option<f64>,
+        #[serde(default)]
+        spoons: Option<u16>,
+    },
+}
+
+impl From<VariantDef> for TaskVariant {
+    fn from(value: VariantDef) -> Self {
+        match value {
+            VariantDef::Name(name) => name.into(),
+            VariantDef::Full {
+                name,
+                weight,
+                spoons,
+            } => Self {
+                name,
+                weight,
+                spoons,
+            },
+        }
+    }
+}
+
+/// A row in a csv file of variants. Untagged enums can't be read from csv files, so they're read
+/// into this first.
+#[derive(Deserialize)]
+struct VariantRecord {
+    name: String,
+    weight: Option<f64>,
+    spoons: Option<u16>,
+}
+
+impl From<VariantRecord> for TaskVariant {
+    fn from(value: VariantRecord) -> Self {
+        Self {
+            name: value.name,
+            weight: value.weight,
+            spoons: value.spoons,
+        }
+    }
+}
+
+impl From<String> for TaskVariant {
+    fn from(name: String) -> Self {
+        Self {
+            name,
+            weight: None,
+            spoons: None,
+        }
+    }
+}
+
+impl FromStr for TaskVariant {
+    type Err = Infallible;
+
+    fn from_str(value: &str) -> std::result::Result<Self, Self::Err> {
+        Ok(String::from(value).into())
+    }
+}
+
+/// A file that a task's variants are read from. Text files have one variant per line, csv files
+/// (or tab-separated tsv and pipe-separated psv files) have a `name` column and optional `weight`
+/// and `spoons` columns, and yaml files have a list of variants written the same way as in the
+/// config. Relative paths are relative to the config
+/// file's directory.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct VariantSource {
+    pub file: Utf8PathBuf,
+    /// If `true`, each variant is only used once; a variant is used up when the task is completed
+    /// with it.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub consume: bool,
+}
+
+impl VariantSource {
+    pub fn path(&self) -> Utf8PathBuf {
+        if let Ok(rest) = self.file.strip_prefix("~")
+            && let Some(home) = dirs::home_dir().and_then(|h| Utf8PathBuf::try_from(h).ok())
+        {
+            home.join(rest)
+        } else if self.file.is_relative() {
+            CONFIG_FILE_PATH
+                .parent()
+                .map(|p| p.join(&self.file))
+                .unwrap_or_else(|| self.file.clone())
+        } else {
+            self.file.clone()
+        }
+    }
+
+    /// Reads the variants from the file.
+    pub fn load(&self) -> Result<Vec<TaskVariant>> {
+        let path = self.path();
+        log::debug!("Reading variants from {path}");
+        match path.extension() {
+            Some("yml") | Some("yaml") => {
+                let data = fs::read(&path)?;
+                Ok(serde_norway::from_slice(&data)?)
+            }
+            Some(ext @ ("csv" | "tsv" | "psv")) => {
+                let delimiter = match ext {
+                    "tsv" => b'\t',
+                    "psv" => b'|',
+                    _ => b',',
+                };
+                Ok(csv::ReaderBuilder::new()
+                    .delimiter(delimiter)
+                    .from_path(&path)?
+                    .into_deserialize::<VariantRecord>()
+                    .map(|r| r.map(TaskVariant::from))
+                    .collect::<std::result::Result<_, _>>()?)
+            }
+            Some("txt") | Some("md") | None => Ok(read_lines(&path)?),
+            Some(ext) => Err(Error::unsupported_file_type(ext)),
+        }
+    }
+}
+
+/// Reads the non-empty lines of a text file, skipping any comments starting with `#`. Markdown
+/// list markers are stripped as well.
+fn read_lines(path: &Utf8Path) -> Result<Vec<TaskVariant>> {
+    Ok(fs::read_to_string(path)?
+        .lines()
+        .map(|l| {
+            let line = l.trim();
+            line.strip_prefix("- ")
+                .or_else(|| line.strip_prefix("* "))
+                .unwrap_or(line)
+                .trim()
+        })
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        .map(|l| TaskVariant::from(String::from(l)))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::State;
+    use pretty_assertions::assert_eq;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    fn temp_dir(name: &str) -> Utf8PathBuf {
+        let dir = Utf8PathBuf::try_from(std::env::temp_dir())
+            .unwrap()
+            .join(format!("randd-tasks-{name}-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn load(file: Utf8PathBuf) -> Vec<TaskVariant> {
+        VariantSource {
+            file,
+            consume: false,
+        }
+        .load()
+        .unwrap()
+    }
+
+    #[test]
+    fn reads_delimited_files() {
+        let dir = temp_dir("delimited");
+        let variant = |name: &str, weight, spoons| TaskVariant {
+            name: String::from(name),
+            weight,
+            spoons,
+        };
+        fs::write(dir.join("a.csv"), "name,weight\n\"Pasta, baked\",2.0\n").unwrap();
+        fs::write(dir.join("a.tsv"), "name\tspoons\nPasta, baked\t3\n").unwrap();
+        fs::write(
+            dir.join("a.psv"),
+            "name|weight|spoons\nPasta, baked|2.0|3\n",
+        )
+        .unwrap();
+        assert_eq!(
+            load(dir.join("a.csv")),
+            [variant("Pasta, baked", Some(2.0), None)]
+        );
+        assert_eq!(
+            load(dir.join("a.tsv")),
+            [variant("Pasta, baked", None, Some(3))]
+        );
+        assert_eq!(
+            load(dir.join("a.psv")),
+            [variant("Pasta, baked", Some(2.0), Some(3))]
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn consumed_variants_stay_used_up_when_the_file_changes() {
+        let dir = temp_dir("consumed");
+        let file = dir.join("recipes.txt");
+        let config = format!(
+            r#"
+cut-off: 04:00:00.0
+limit-by:
+  tasks: 1
+tasks:
+- {{ slug: cook, task: Cook, weight: 1.0, spoons: 1, variants-from: {{ file: "{file}", consume: true }} }}
+"#
+        );
+        let available = |state: &State| -> Vec<String> {
+            let task = state.get_task("cook").unwrap();
+            let mut names: Vec<_> = task
+                .available_variants()
+                .unwrap()
+                .into_iter()
+                .map(|(_, v)| v.name)
+                .collect();
+            names.sort();
+            names
+        };
+
+        fs::write(&file, "curry\nstew\n").unwrap();
+        let state = State::from_config_yaml(&config);
+        let task = state.get_task("cook").unwrap();
+        task.pick_variant(None, &mut ChaCha8Rng::seed_from_u64(1))
+            .unwrap();
+        task.complete();
+        let cooked = task.state.borrow().variant.clone().unwrap();
+        let consumed = task.state.borrow().consumed.clone();
+        assert_eq!(Vec::from_iter(&consumed), [&cooked]);
+
+        // A variant added to the start of the file doesn't shift which ones are used up.
+        fs::write(&file, "soup\ncurry\nstew\n").unwrap();
+        let state = State::from_config_yaml(&config);
+        state.get_task("cook").unwrap().state.borrow_mut().consumed = consumed;
+        let mut expected = vec!["curry", "soup", "stew"];
+        expected.retain(|name| *name != cooked);
+        assert_eq!(available(&state), expected);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
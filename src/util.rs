@@ -1,6 +1,7 @@
+use crate::{Error, Result};
 use serde::{Deserialize, Serialize};
-use std::{borrow::Borrow, cell::RefCell, ops::Deref, rc::Rc};
-use time::{Date, Duration, OffsetDateTime, Time, UtcOffset, macros::time};
+use std::{borrow::Borrow, cell::RefCell, fmt, ops::Deref, rc::Rc, str::FromStr};
+use time::{Date, Duration, Month, OffsetDateTime, Time, UtcOffset, Weekday, macros::time};
 
 lazy_static! {
     pub(crate) static ref LOCAL_OFFSET: UtcOffset = UtcOffset::current_local_offset().unwrap();
@@ -43,6 +44,117 @@ pub fn days_elapsed(d1: Date, d2: Date) -> i64 {
     elapsed.whole_days()
 }
 
+/// Parses a weekday from either its full name or its three letter abbreviation, ignoring case.
+pub fn parse_weekday(value: &str) -> Result<Weekday> {
+    let weekday = match value.to_lowercase().as_str() {
+        "mon" | "monday" => Weekday::Monday,
+        "tue" | "tuesday" => Weekday::Tuesday,
+        "wed" | "wednesday" => Weekday::Wednesday,
+        "thu" | "thursday" => Weekday::Thursday,
+        "fri" | "friday" => Weekday::Friday,
+        "sat" | "saturday" => Weekday::Saturday,
+        "sun" | "sunday" => Weekday::Sunday,
+        _ => {
+            return Err(Error::simple(format!(
+                "'{value}' is not a day of the week."
+            )));
+        }
+    };
+    Ok(weekday)
+}
+
+/// A day of the year without a year, such as `04-01` for April 1st.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct MonthDay {
+    pub month: Month,
+    pub day: u8,
+}
+
+impl MonthDay {
+    pub fn of(date: Date) -> Self {
+        Self {
+            month: date.month(),
+            day: date.day(),
+        }
+    }
+
+    /// Returns `true` if the date falls within the window starting on `from` and ending on `until`
+    /// (both inclusive). The window wraps around the end of the year if `from` comes after
+    /// `until`, and a missing end means the window is open on that side.
+    pub fn in_window(date: Date, from: Option<Self>, until: Option<Self>) -> bool {
+        let month_day = Self::of(date);
+        match (from, until) {
+            (Some(from), Some(until)) if from <= until => from <= month_day && month_day <= until,
+            (Some(from), Some(until)) => from <= month_day || month_day <= until,
+            (Some(from), None) => from <= month_day,
+            (None, Some(until)) => month_day <= until,
+            (None, None) => true,
+        }
+    }
+}
+
+impl FromStr for MonthDay {
+    type Err = Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        let invalid = || Error::simple(format!("'{value}' is not a valid month-day (MM-DD)."));
+        let (month, day) = value.split_once('-').ok_or_else(invalid)?;
+        let month = month
+            .parse::<u8>()
+            .ok()
+            .and_then(|m| Month::try_from(m).ok())
+            .ok_or_else(invalid)?;
+        let day = day.parse::<u8>().map_err(|_| invalid())?;
+        // Use a leap year so that February 29th is allowed.
+        if day == 0 || day > month.length(2024) {
+            return Err(invalid());
+        }
+        Ok(Self { month, day })
+    }
+}
+
+impl TryFrom<String> for MonthDay {
+    type Error = Error;
+
+    fn try_from(value: String) -> Result<Self> {
+        value.parse()
+    }
+}
+
+impl From<MonthDay> for String {
+    fn from(value: MonthDay) -> Self {
+        value.to_string()
+    }
+}
+
+impl fmt::Display for MonthDay {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:02}-{:02}", self.month as u8, self.day)
+    }
+}
+
+#[inline]
+pub(crate) fn is_zero(value: &usize) -> bool {
+    *value == 0
+}
+
+#[inline]
+pub(crate) fn is_zero_f64(value: &f64) -> bool {
+    *value == 0.0
+}
+
+/// Hashes the given bytes using 64-bit FNV-1a. Unlike the standard library's hashers, this is
+/// guaranteed to give the same result across runs, platforms, and versions of Rust.
+pub fn stable_hash<B: AsRef<[u8]>>(bytes: B) -> u64 {
+    bytes
+        .as_ref()
+        .iter()
+        .fold(0xcbf29ce484222325, |hash, byte| {
+            (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+        })
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize, Default)]
 /// A new type that wraps a `Rc<RefCell<V>>`. This is _super_ useful in our case because we're
 /// often referring to the same object across different objects (mostly because our state has a
@@ -81,3 +193,76 @@ impl<V> Borrow<RefCell<V>> for RcCell<V> {
         self.0.deref()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use time::macros::date;
+
+    fn month_day(value: &str) -> Option<MonthDay> {
+        Some(value.parse().unwrap())
+    }
+
+    #[test]
+    fn window_within_year() {
+        let (from, until) = (month_day("04-01"), month_day("09-30"));
+        assert!(MonthDay::in_window(date!(2026 - 04 - 01), from, until));
+        assert!(MonthDay::in_window(date!(2026 - 09 - 30), from, until));
+        assert!(!MonthDay::in_window(date!(2026 - 03 - 31), from, until));
+        assert!(!MonthDay::in_window(date!(2026 - 10 - 01), from, until));
+    }
+
+    #[test]
+    fn window_wraps_around_year() {
+        let (from, until) = (month_day("11-15"), month_day("02-28"));
+        assert!(MonthDay::in_window(date!(2026 - 12 - 31), from, until));
+        assert!(MonthDay::in_window(date!(2027 - 01 - 01), from, until));
+        assert!(MonthDay::in_window(date!(2026 - 11 - 15), from, until));
+        assert!(!MonthDay::in_window(date!(2026 - 03 - 01), from, until));
+        assert!(!MonthDay::in_window(date!(2026 - 11 - 14), from, until));
+    }
+
+    #[test]
+    fn open_windows() {
+        assert!(MonthDay::in_window(
+            date!(2026 - 12 - 31),
+            month_day("06-01"),
+            None
+        ));
+        assert!(!MonthDay::in_window(
+            date!(2026 - 05 - 31),
+            month_day("06-01"),
+            None
+        ));
+        assert!(MonthDay::in_window(
+            date!(2026 - 01 - 01),
+            None,
+            month_day("06-01")
+        ));
+        assert!(!MonthDay::in_window(
+            date!(2026 - 06 - 02),
+            None,
+            month_day("06-01")
+        ));
+        assert!(MonthDay::in_window(date!(2026 - 06 - 02), None, None));
+    }
+
+    #[test]
+    fn parses_month_days() {
+        assert_eq!(
+            "02-29".parse::<MonthDay>().unwrap(),
+            MonthDay {
+                month: Month::February,
+                day: 29
+            }
+        );
+        assert_eq!("4-1".parse::<MonthDay>().unwrap().to_string(), "04-01");
+        for value in ["02-30", "13-01", "00-10", "04-00", "0401"] {
+            assert!(
+                value.parse::<MonthDay>().is_err(),
+                "{value} should be invalid"
+            );
+        }
+    }
+}